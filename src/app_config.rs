@@ -1,21 +1,543 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::types::VehicleClass;
+
 pub const MIN_GREEN_TIME: f32 = 0.5;
-pub const CAR_SPEED: f32 = 2.5;
+pub const ACTUATED_MIN_GREEN: f32 = 5.0;
+pub const PASSAGE_TIME: f32 = 2.0;
+pub const MAX_PRESSURE_MIN_PHASE: f32 = 5.0;
+pub const MAX_GREEN_TIME: f32 = 20.0;
+pub const SATURATION_FLOW: f32 = 1.2;
+pub const STARTUP_LOST_TIME: f32 = 2.0;
+pub const AMBER_EXTENSION: f32 = 2.0;
+pub const WEBSTER_MIN_CYCLE: f32 = 30.0;
+pub const WEBSTER_MAX_CYCLE: f32 = 120.0;
+pub const WEBSTER_MIN_GREEN: f32 = 5.0;
+pub const MAX_WAIT_TIME: f32 = 45.0;
+pub const WAIT_WEIGHT: f32 = 0.5;
+pub const AMBER_TIME: f32 = 2.0;
+pub const ALL_RED_TIME: f32 = 1.0;
+pub const CAR_SPEED: f32 = 150.0;
+pub const MAX_ACCEL: f32 = 90.0;
+pub const COMFORT_DECEL: f32 = 150.0;
+pub const MIN_GAP: f32 = 8.0;
+pub const TIME_HEADWAY: f32 = 0.5;
+pub const IDM_DELTA: f32 = 4.0;
+pub const STOPPED_SPEED: f32 = 5.0;
+
+pub const FIXED_DT: f32 = 1.0 / 60.0;
+pub const HEADLESS_BOUNDS: (f32, f32) = (800.0, 800.0);
+pub const HEADLESS_DURATION: f32 = 600.0;
+pub const HEADLESS_SPAWN_INTERVAL: f32 = 1.0;
+pub const ENV_DECISION_INTERVAL: f32 = 5.0;
+pub const MAX_STEPS_PER_FRAME: u32 = 8;
 
-pub const CAR_SIZE: f32 = 24.0;
-pub const VEHICLE_LENGTH: f32 = CAR_SIZE;
+pub const VEHICLE_LENGTH: f32 = 26.0;
+pub const VEHICLE_WIDTH: f32 = 14.0;
+pub const CAR_SPAWN_WEIGHT: f32 = 0.7;
+pub const TRUCK_LENGTH: f32 = 44.0;
+pub const TRUCK_WIDTH: f32 = 17.0;
+pub const TRUCK_MAX_SPEED: f32 = 120.0;
+pub const TRUCK_MAX_ACCEL: f32 = 45.0;
+pub const TRUCK_SPAWN_WEIGHT: f32 = 0.1;
+pub const BUS_LENGTH: f32 = 52.0;
+pub const BUS_WIDTH: f32 = 17.0;
+pub const BUS_MAX_SPEED: f32 = 115.0;
+pub const BUS_MAX_ACCEL: f32 = 50.0;
+pub const BUS_SPAWN_WEIGHT: f32 = 0.1;
+pub const MOTORCYCLE_LENGTH: f32 = 16.0;
+pub const MOTORCYCLE_WIDTH: f32 = 7.0;
+pub const MOTORCYCLE_MAX_SPEED: f32 = 165.0;
+pub const MOTORCYCLE_MAX_ACCEL: f32 = 140.0;
+pub const MOTORCYCLE_SPAWN_WEIGHT: f32 = 0.1;
 pub const SAFETY_GAP: f32 = 30.0;
 pub const LANE_LENGTH: f32 = 400.0;
-pub const LANE_CAPACITY: usize = (LANE_LENGTH / (VEHICLE_LENGTH + SAFETY_GAP)) as usize;
 
 pub const ROAD_WIDTH: f32 = 140.0;
 pub const LANE_OFFSET: f32 = 45.0;
+pub const LANE_CENTER: f32 = 25.0;
 
 pub const CENTER_HALF: f32 = 60.0;
 pub const DESPAWN_OFFSET: f32 = 120.0;
-pub const SPAWN_DISTANCE_CHECK: f32 = 40.0;
-pub const AHEAD_CHECK_DISTANCE: f32 = 50.0;
-pub const AHEAD_CHECK_LATERAL: f32 = 18.0;
+pub const SPAWN_MARGIN: f32 = 20.0;
+pub const SPAWN_DISTANCE_CHECK: f32 = 16.0;
+pub const AHEAD_CHECK_DISTANCE: f32 = 160.0;
+pub const AHEAD_CHECK_LATERAL: f32 = 2.0;
+pub const CONFLICT_DISTANCE: f32 = 20.0;
+pub const CRITICAL_GAP: f32 = 4.0;
+pub const YIELD_MARGIN: f32 = 2.0;
+
+pub const PRESENCE_LENGTH: f32 = 30.0;
+pub const PASSAGE_DISTANCE: f32 = 120.0;
+pub const PASSAGE_LENGTH: f32 = 6.0;
+pub const DETECTOR_WIDTH: f32 = 24.0;
+
+pub const LIGHT_SIZE: f32 = 16.0;
+pub const LIGHT_PADDING: f32 = 4.0;
+
+// The runtime settings of a simulation; the constants above are the defaults.
+// Controller and timing settings only seed the parameters of a run, so a
+// scenario or a --params file still overrides them per controller.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimConfig {
+    pub car_speed: f32,
+    pub max_accel: f32,
+    pub comfort_decel: f32,
+    pub min_gap: f32,
+    pub time_headway: f32,
+    pub idm_delta: f32,
+    pub stopped_speed: f32,
+
+    pub fixed_dt: f32,
+    pub max_steps_per_frame: u32,
+
+    pub vehicle_length: f32,
+    pub vehicle_width: f32,
+    pub safety_gap: f32,
+    pub lane_length: f32,
+
+    pub road_width: f32,
+    pub lane_offset: f32,
+    pub lane_center: f32,
+
+    pub center_half: f32,
+    pub despawn_offset: f32,
+    pub spawn_margin: f32,
+    pub spawn_distance_check: f32,
+    pub ahead_check_distance: f32,
+    pub ahead_check_lateral: f32,
+    pub conflict_distance: f32,
+    pub critical_gap: f32,
+    pub yield_margin: f32,
+
+    pub presence_length: f32,
+    pub passage_distance: f32,
+    pub passage_length: f32,
+    pub detector_width: f32,
+
+    pub light_size: f32,
+    pub light_padding: f32,
+
+    pub classes: ClassConfig,
+    pub controller: ControllerConfig,
+    pub timing: TimingConfig,
+    pub headless: HeadlessConfig,
+}
+
+// The vehicle classes other than the car, which uses the vehicle settings
+// above, and how often each class spawns.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClassConfig {
+    pub car_spawn_weight: f32,
+    pub truck_length: f32,
+    pub truck_width: f32,
+    pub truck_max_speed: f32,
+    pub truck_max_accel: f32,
+    pub truck_spawn_weight: f32,
+    pub bus_length: f32,
+    pub bus_width: f32,
+    pub bus_max_speed: f32,
+    pub bus_max_accel: f32,
+    pub bus_spawn_weight: f32,
+    pub motorcycle_length: f32,
+    pub motorcycle_width: f32,
+    pub motorcycle_max_speed: f32,
+    pub motorcycle_max_accel: f32,
+    pub motorcycle_spawn_weight: f32,
+}
+
+impl Default for ClassConfig {
+    fn default() -> Self {
+        Self {
+            car_spawn_weight: CAR_SPAWN_WEIGHT,
+            truck_length: TRUCK_LENGTH,
+            truck_width: TRUCK_WIDTH,
+            truck_max_speed: TRUCK_MAX_SPEED,
+            truck_max_accel: TRUCK_MAX_ACCEL,
+            truck_spawn_weight: TRUCK_SPAWN_WEIGHT,
+            bus_length: BUS_LENGTH,
+            bus_width: BUS_WIDTH,
+            bus_max_speed: BUS_MAX_SPEED,
+            bus_max_accel: BUS_MAX_ACCEL,
+            bus_spawn_weight: BUS_SPAWN_WEIGHT,
+            motorcycle_length: MOTORCYCLE_LENGTH,
+            motorcycle_width: MOTORCYCLE_WIDTH,
+            motorcycle_max_speed: MOTORCYCLE_MAX_SPEED,
+            motorcycle_max_accel: MOTORCYCLE_MAX_ACCEL,
+            motorcycle_spawn_weight: MOTORCYCLE_SPAWN_WEIGHT,
+        }
+    }
+}
+
+// Defaults of the adaptive controllers' parameters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ControllerConfig {
+    pub min_green: f32,
+    pub max_green: f32,
+    pub max_wait: f32,
+    pub wait_weight: f32,
+    pub actuated_min_green: f32,
+    pub passage_time: f32,
+    pub max_pressure_min_phase: f32,
+}
+
+impl Default for ControllerConfig {
+    fn default() -> Self {
+        Self {
+            min_green: MIN_GREEN_TIME,
+            max_green: MAX_GREEN_TIME,
+            max_wait: MAX_WAIT_TIME,
+            wait_weight: WAIT_WEIGHT,
+            actuated_min_green: ACTUATED_MIN_GREEN,
+            passage_time: PASSAGE_TIME,
+            max_pressure_min_phase: MAX_PRESSURE_MIN_PHASE,
+        }
+    }
+}
+
+// Clearance intervals and the inputs of Webster's method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimingConfig {
+    pub amber_time: f32,
+    pub all_red_time: f32,
+    pub saturation_flow: f32,
+    pub startup_lost_time: f32,
+    pub amber_extension: f32,
+    pub webster_min_green: f32,
+    pub webster_min_cycle: f32,
+    pub webster_max_cycle: f32,
+}
+
+impl Default for TimingConfig {
+    fn default() -> Self {
+        Self {
+            amber_time: AMBER_TIME,
+            all_red_time: ALL_RED_TIME,
+            saturation_flow: SATURATION_FLOW,
+            startup_lost_time: STARTUP_LOST_TIME,
+            amber_extension: AMBER_EXTENSION,
+            webster_min_green: WEBSTER_MIN_GREEN,
+            webster_min_cycle: WEBSTER_MIN_CYCLE,
+            webster_max_cycle: WEBSTER_MAX_CYCLE,
+        }
+    }
+}
+
+// Runs without a window: headless runs and the learning environment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HeadlessConfig {
+    pub bounds: (f32, f32),
+    pub duration: f32,
+    pub spawn_interval: f32,
+    pub decision_interval: f32,
+}
+
+impl Default for HeadlessConfig {
+    fn default() -> Self {
+        Self {
+            bounds: HEADLESS_BOUNDS,
+            duration: HEADLESS_DURATION,
+            spawn_interval: HEADLESS_SPAWN_INTERVAL,
+            decision_interval: ENV_DECISION_INTERVAL,
+        }
+    }
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            car_speed: CAR_SPEED,
+            max_accel: MAX_ACCEL,
+            comfort_decel: COMFORT_DECEL,
+            min_gap: MIN_GAP,
+            time_headway: TIME_HEADWAY,
+            idm_delta: IDM_DELTA,
+            stopped_speed: STOPPED_SPEED,
+            fixed_dt: FIXED_DT,
+            max_steps_per_frame: MAX_STEPS_PER_FRAME,
+            vehicle_length: VEHICLE_LENGTH,
+            vehicle_width: VEHICLE_WIDTH,
+            safety_gap: SAFETY_GAP,
+            lane_length: LANE_LENGTH,
+            road_width: ROAD_WIDTH,
+            lane_offset: LANE_OFFSET,
+            lane_center: LANE_CENTER,
+            center_half: CENTER_HALF,
+            despawn_offset: DESPAWN_OFFSET,
+            spawn_margin: SPAWN_MARGIN,
+            spawn_distance_check: SPAWN_DISTANCE_CHECK,
+            ahead_check_distance: AHEAD_CHECK_DISTANCE,
+            ahead_check_lateral: AHEAD_CHECK_LATERAL,
+            conflict_distance: CONFLICT_DISTANCE,
+            critical_gap: CRITICAL_GAP,
+            yield_margin: YIELD_MARGIN,
+            presence_length: PRESENCE_LENGTH,
+            passage_distance: PASSAGE_DISTANCE,
+            passage_length: PASSAGE_LENGTH,
+            detector_width: DETECTOR_WIDTH,
+            light_size: LIGHT_SIZE,
+            light_padding: LIGHT_PADDING,
+            classes: ClassConfig::default(),
+            controller: ControllerConfig::default(),
+            timing: TimingConfig::default(),
+            headless: HeadlessConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "cannot read configuration: {}", e),
+            ConfigError::Parse(e) => write!(f, "cannot parse configuration: {}", e),
+            ConfigError::Invalid(msg) => write!(f, "invalid configuration: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl SimConfig {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(path).map_err(ConfigError::Io)?;
+        Self::from_toml(&text)
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        let config: SimConfig = toml::from_str(text).map_err(ConfigError::Parse)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).expect("configurations always serialize")
+    }
+
+    // Command-line form `name=value`, e.g. `road_width=160`. Settings in a
+    // section take its name as a prefix, `controller.min_green=6`, which can
+    // be left out when no other section has a setting of that name. The
+    // result is not validated, so several settings can change together.
+    pub fn set(&mut self, setting: &str) -> Result<(), ConfigError> {
+        let (name, value) = setting
+            .split_once('=')
+            .ok_or_else(|| ConfigError::Invalid(format!("'{}' is not of the form name=value", setting)))?;
+        let (name, value) = (name.trim(), value.trim());
+
+        let mut table = toml::Table::try_from(&*self).expect("configurations always serialize");
+        let (section, key) = Self::resolve(&table, name)?;
+
+        let mut parsed: toml::Table = toml::from_str(&format!("value = {}", value))
+            .map_err(|_| ConfigError::Invalid(format!("'{}' is not a valid value for {}", value, name)))?;
+        let target = match section {
+            Some(section) => table[section.as_str()].as_table_mut().expect("sections are tables"),
+            None => &mut table,
+        };
+        target.insert(key, parsed.remove("value").expect("parsed above"));
+        *self = table
+            .try_into()
+            .map_err(|e: toml::de::Error| ConfigError::Invalid(format!("{}: {}", name, e.message())))?;
+        Ok(())
+    }
+
+    // The section, if any, and the key a setting name refers to.
+    fn resolve(table: &toml::Table, name: &str) -> Result<(Option<String>, String), ConfigError> {
+        let unknown = || ConfigError::Invalid(format!("unknown setting '{}'", name));
+
+        if let Some((section, key)) = name.split_once('.') {
+            let found = table.get(section).and_then(|v| v.as_table()).is_some_and(|t| t.contains_key(key));
+            return if found { Ok((Some(section.into()), key.into())) } else { Err(unknown()) };
+        }
+
+        match table.get(name) {
+            Some(toml::Value::Table(_)) => {
+                return Err(ConfigError::Invalid(format!("'{}' is a section, set e.g. {}.<name>", name, name)));
+            }
+            Some(_) => return Ok((None, name.into())),
+            None => {}
+        }
+
+        let sections: Vec<&String> = table
+            .iter()
+            .filter(|(_, v)| v.as_table().is_some_and(|t| t.contains_key(name)))
+            .map(|(section, _)| section)
+            .collect();
+        match sections.as_slice() {
+            [] => Err(unknown()),
+            [section] => Ok((Some((*section).clone()), name.into())),
+            _ => Err(ConfigError::Invalid(format!(
+                "'{}' is in several sections, set it as {}.{}",
+                name, sections[0], name
+            ))),
+        }
+    }
+
+    // How many vehicles fit on an approach, bumper to bumper with the safety gap.
+    pub fn lane_capacity(&self) -> usize {
+        (self.lane_length / (self.vehicle_length + self.safety_gap)) as usize
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |msg: String| Err(ConfigError::Invalid(msg));
+
+        let positive = [
+            ("car_speed", self.car_speed),
+            ("max_accel", self.max_accel),
+            ("comfort_decel", self.comfort_decel),
+            ("idm_delta", self.idm_delta),
+            ("fixed_dt", self.fixed_dt),
+            ("vehicle_length", self.vehicle_length),
+            ("vehicle_width", self.vehicle_width),
+            ("lane_length", self.lane_length),
+            ("road_width", self.road_width),
+            ("center_half", self.center_half),
+            ("ahead_check_distance", self.ahead_check_distance),
+            ("presence_length", self.presence_length),
+            ("passage_length", self.passage_length),
+            ("classes.truck_length", self.classes.truck_length),
+            ("classes.truck_width", self.classes.truck_width),
+            ("classes.truck_max_speed", self.classes.truck_max_speed),
+            ("classes.truck_max_accel", self.classes.truck_max_accel),
+            ("classes.bus_length", self.classes.bus_length),
+            ("classes.bus_width", self.classes.bus_width),
+            ("classes.bus_max_speed", self.classes.bus_max_speed),
+            ("classes.bus_max_accel", self.classes.bus_max_accel),
+            ("classes.motorcycle_length", self.classes.motorcycle_length),
+            ("classes.motorcycle_width", self.classes.motorcycle_width),
+            ("classes.motorcycle_max_speed", self.classes.motorcycle_max_speed),
+            ("classes.motorcycle_max_accel", self.classes.motorcycle_max_accel),
+            ("controller.min_green", self.controller.min_green),
+            ("controller.max_green", self.controller.max_green),
+            ("controller.max_wait", self.controller.max_wait),
+            ("controller.actuated_min_green", self.controller.actuated_min_green),
+            ("controller.passage_time", self.controller.passage_time),
+            ("controller.max_pressure_min_phase", self.controller.max_pressure_min_phase),
+            ("timing.saturation_flow", self.timing.saturation_flow),
+            ("timing.webster_min_green", self.timing.webster_min_green),
+            ("timing.webster_min_cycle", self.timing.webster_min_cycle),
+            ("timing.webster_max_cycle", self.timing.webster_max_cycle),
+            ("headless.bounds", self.headless.bounds.0.min(self.headless.bounds.1)),
+            ("headless.duration", self.headless.duration),
+            ("headless.spawn_interval", self.headless.spawn_interval),
+            ("headless.decision_interval", self.headless.decision_interval),
+        ];
+        if let Some((name, value)) = positive.iter().find(|(_, v)| !(v.is_finite() && *v > 0.0)) {
+            return invalid(format!("{} must be positive, got {}", name, value));
+        }
+
+        let non_negative = [
+            ("min_gap", self.min_gap),
+            ("time_headway", self.time_headway),
+            ("stopped_speed", self.stopped_speed),
+            ("safety_gap", self.safety_gap),
+            ("lane_offset", self.lane_offset),
+            ("lane_center", self.lane_center),
+            ("despawn_offset", self.despawn_offset),
+            ("spawn_margin", self.spawn_margin),
+            ("spawn_distance_check", self.spawn_distance_check),
+            ("ahead_check_lateral", self.ahead_check_lateral),
+            ("conflict_distance", self.conflict_distance),
+            ("critical_gap", self.critical_gap),
+            ("yield_margin", self.yield_margin),
+            ("passage_distance", self.passage_distance),
+            ("detector_width", self.detector_width),
+            ("light_size", self.light_size),
+            ("light_padding", self.light_padding),
+            ("classes.car_spawn_weight", self.classes.car_spawn_weight),
+            ("classes.truck_spawn_weight", self.classes.truck_spawn_weight),
+            ("classes.bus_spawn_weight", self.classes.bus_spawn_weight),
+            ("classes.motorcycle_spawn_weight", self.classes.motorcycle_spawn_weight),
+            ("controller.wait_weight", self.controller.wait_weight),
+            ("timing.amber_time", self.timing.amber_time),
+            ("timing.all_red_time", self.timing.all_red_time),
+            ("timing.startup_lost_time", self.timing.startup_lost_time),
+            ("timing.amber_extension", self.timing.amber_extension),
+        ];
+        if let Some((name, value)) = non_negative.iter().find(|(_, v)| !(v.is_finite() && *v >= 0.0)) {
+            return invalid(format!("{} cannot be negative, got {}", name, value));
+        }
+
+        let specs = VehicleClass::ALL.map(|class| (class, class.spec(self)));
+        if specs.iter().all(|(_, spec)| spec.spawn_weight == 0.0) {
+            return invalid("at least one vehicle class needs a positive spawn weight".into());
+        }
+
+        let half_road = self.road_width / 2.0;
+        if self.lane_offset >= half_road {
+            return invalid(format!(
+                "lane_offset {} puts the lane line outside a road {} wide",
+                self.lane_offset, self.road_width
+            ));
+        }
+        let (widest, wide) = specs.iter().max_by(|a, b| a.1.width.total_cmp(&b.1.width)).expect("there are classes");
+        if self.lane_center + wide.width / 2.0 > half_road {
+            return invalid(format!(
+                "a {} {} wide centred {} from the middle does not fit on a road {} wide",
+                widest.name().to_lowercase(),
+                wide.width,
+                self.lane_center,
+                self.road_width
+            ));
+        }
+        if self.center_half > half_road {
+            return invalid(format!(
+                "center_half {} is larger than half the road width {}",
+                self.center_half, half_road
+            ));
+        }
+        let (longest, long) = specs.iter().max_by(|a, b| a.1.length.total_cmp(&b.1.length)).expect("there are classes");
+        if long.length + self.safety_gap > self.lane_length {
+            return invalid(format!(
+                "a lane {} long holds no {} of length {} with a safety gap of {}",
+                self.lane_length,
+                longest.name().to_lowercase(),
+                long.length,
+                self.safety_gap
+            ));
+        }
+        if self.passage_distance + self.passage_length > self.lane_length {
+            return invalid(format!(
+                "the passage detector ends {} upstream, beyond the lane length {}",
+                self.passage_distance + self.passage_length,
+                self.lane_length
+            ));
+        }
+        if self.stopped_speed >= self.car_speed {
+            return invalid(format!(
+                "stopped_speed {} must be below car_speed {}",
+                self.stopped_speed, self.car_speed
+            ));
+        }
+        if self.controller.max_green < self.controller.min_green.max(self.controller.actuated_min_green) {
+            return invalid(format!(
+                "controller.max_green {} is shorter than a minimum green",
+                self.controller.max_green
+            ));
+        }
+        if self.timing.webster_max_cycle < self.timing.webster_min_cycle {
+            return invalid(format!(
+                "timing.webster_max_cycle {} is shorter than webster_min_cycle {}",
+                self.timing.webster_max_cycle, self.timing.webster_min_cycle
+            ));
+        }
+        if self.max_steps_per_frame == 0 {
+            return invalid("max_steps_per_frame must be at least 1".into());
+        }
 
-pub const LIGHT_OFFSET: f32 = 85.0;
-pub const LIGHT_SIZE: f32 = 36.0;
-pub const LIGHT_PADDING: f32 = 6.0;
+        Ok(())
+    }
+}
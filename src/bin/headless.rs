@@ -0,0 +1,319 @@
+use std::fs;
+use std::process::exit;
+
+use road_intersection::control::actuated::ActuatedParams;
+use road_intersection::control::demand_based::DemandParams;
+use road_intersection::control::fixed_time::FixedTimeController;
+use road_intersection::control::webster::{WebsterParams, WebsterTiming};
+use road_intersection::optimize::genetic::{GeneticParams, GeneticSearch};
+use road_intersection::optimize::timing::{Objective, TimingTarget};
+use road_intersection::{ConflictMatrix, ControllerSpec, DemandProfile, Headway, HeadlessRun, TurningMatrix, Movement, Origin, Phase, Route, Scenario, SignalController, SignalPlan, SimConfig, Simulation};
+
+const USAGE: &str = "\
+usage: headless <command> [options]
+
+commands:
+  run        simulate and print statistics
+  webster    simulate, measure flows, write a Webster fixed-time plan and re-simulate with it
+  optimize   tune a plan or controller with a genetic algorithm over parallel runs
+
+options:
+  --scenario <file>        start from a scenario; the options below override it
+  --config <file>          simulation settings (geometry, vehicle dynamics, time step)
+  --set <name>=<value>     change one setting, e.g. --set road_width=160; repeatable
+  --seed <n>               random seed (default 0)
+  --duration <s>           simulated seconds
+  --spawn-interval <s>     seconds between spawn attempts when no demand is given
+  --demand <file>          arrivals from a demand profile
+  --rate <veh/h>           the same arrival rate on every approach
+  --turning <file>         turning ratios per approach (default: even split)
+  --headway <kind>         exponential, shifted:<min>, erlang:<k> or uniform:<spread> (with --rate)
+  --controller <name>      demand, actuated or max-pressure (default demand, or the scenario's)
+  --params <file>          parameters for the demand or actuated controller
+  --plan <file>            run a fixed-time plan instead of a controller
+  --out <file>             where webster or optimize write their result
+
+optimize options:
+  --target <name>          plan, demand or actuated (default plan)
+  --plan <file>            plan whose cycle and splits are tuned (default four-phase)
+  --objective <name>       delay or throughput (default delay)
+  --seeds <n>              runs averaged per candidate, seeds from --seed on (default 2)
+  --population <n>         candidates per generation
+  --generations <n>        number of generations
+  --threads <n>            parallel simulations (default: all cores)
+  --log <file>             convergence log (default convergence.csv)";
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{}", message);
+    exit(1);
+}
+
+fn option<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == name)
+        .map(|i| args.get(i + 1).unwrap_or_else(|| fail(format!("{} expects a value", name))).as_str())
+}
+
+fn number<T: std::str::FromStr>(args: &[String], name: &str) -> Option<T> {
+    option(args, name).map(|v| v.parse().unwrap_or_else(|_| fail(format!("{} expects a number, got '{}'", name, v))))
+}
+
+fn parse_demand(args: &[String]) -> Option<DemandProfile> {
+    if let Some(path) = option(args, "--demand") {
+        return Some(DemandProfile::load(path).unwrap_or_else(|e| fail(format!("{}: {}", path, e))));
+    }
+
+    let rate: f32 = number(args, "--rate")?;
+    let headway: Headway = option(args, "--headway")
+        .map(|h| h.parse().unwrap_or_else(|e| fail(e)))
+        .unwrap_or_default();
+    let profile = DemandProfile::constant(rate, headway);
+    profile.validate().unwrap_or_else(|e| fail(format!("--rate: {}", e)));
+    Some(profile)
+}
+
+// The scenario with --config and --set applied; without --scenario an empty
+// one, so the overrides still reach the default run and controller.
+fn parse_scenario(args: &[String]) -> Scenario {
+    let configure = |config| Ok(parse_config(args, config));
+    match option(args, "--scenario") {
+        Some(path) => Scenario::load_with(path, configure).unwrap_or_else(|e| fail(format!("{}: {}", path, e))),
+        None => Scenario::from_toml_with("", configure).unwrap_or_else(|e| fail(e)),
+    }
+}
+
+// The scenario's settings, replaced by --config and then adjusted by each --set.
+fn parse_config(args: &[String], base: SimConfig) -> SimConfig {
+    let mut config = match option(args, "--config") {
+        Some(path) => SimConfig::load(path).unwrap_or_else(|e| fail(format!("{}: {}", path, e))),
+        None => base,
+    };
+    for setting in args.windows(2).filter(|w| w[0] == "--set").map(|w| &w[1]) {
+        config.set(setting).unwrap_or_else(|e| fail(e));
+    }
+    config.validate().unwrap_or_else(|e| fail(e));
+    config
+}
+
+fn parse_run(args: &[String], scenario: &Scenario) -> HeadlessRun {
+    let mut run = HeadlessRun::from_scenario(scenario);
+    if let Some(seed) = number(args, "--seed") {
+        run.seed = seed;
+    }
+    if let Some(duration) = number::<f32>(args, "--duration") {
+        if !(duration.is_finite() && duration > 0.0) {
+            fail(format!("--duration must be positive, got {}", duration));
+        }
+        run.duration = duration;
+    }
+    if let Some(interval) = number::<f32>(args, "--spawn-interval") {
+        if !(interval.is_finite() && interval > 0.0) {
+            fail(format!("--spawn-interval must be positive, got {}", interval));
+        }
+        run.spawn_interval = interval;
+    }
+    if let Some(demand) = parse_demand(args) {
+        run.demand = Some(demand);
+    }
+    if let Some(path) = option(args, "--turning") {
+        run.turning = TurningMatrix::load(path).unwrap_or_else(|e| fail(format!("{}: {}", path, e)));
+    }
+    run
+}
+
+// Parameters left out of --params come from the configuration, and are
+// checked like a scenario's controller section.
+fn parse_controller(args: &[String], scenario: &Scenario) -> Box<dyn SignalController> {
+    if let Some(path) = option(args, "--plan") {
+        let plan = SignalPlan::load_with(path, &scenario.config).unwrap_or_else(|e| fail(format!("{}: {}", path, e)));
+        return Box::new(FixedTimeController::new(plan));
+    }
+
+    let Some(name) = option(args, "--controller") else {
+        return scenario.controller.build();
+    };
+    if ControllerSpec::from_config(name, &scenario.config).is_none() {
+        fail(format!("unknown controller '{}'", name));
+    }
+
+    let path = option(args, "--params");
+    let mut table = match path {
+        Some(path) => {
+            let text = fs::read_to_string(path).unwrap_or_else(|e| fail(format!("{}: {}", path, e)));
+            toml::from_str(&text).unwrap_or_else(|e| fail(format!("{}: {}", path, e)))
+        }
+        None => toml::Table::new(),
+    };
+    table.insert("kind".into(), name.into());
+    let spec = ControllerSpec::from_table(table, &scenario.config)
+        .map_err(|e| e.to_string())
+        .and_then(|spec| spec.validate(&scenario.config).map(|()| spec))
+        .unwrap_or_else(|e| fail(format!("{}: {}", path.unwrap_or(name), e)));
+    spec.build()
+}
+
+fn print_summary(label: &str, simulation: &Simulation) {
+    let statistics = simulation.statistics();
+    let total = statistics.total();
+    let queued: usize = Origin::ALL.into_iter().map(|o| simulation.entry_queue(o)).sum();
+    println!(
+        "{}: {} spawned, {} completed, {} still waiting to enter, avg travel {:.1}s, avg delay {:.1}s incl. {:.1}s entry wait (left {:.1}s, straight {:.1}s, right {:.1}s)",
+        label,
+        total.spawned,
+        total.completed,
+        queued,
+        total.average_travel_time(),
+        total.average_delay(),
+        total.average_entry_wait(),
+        statistics.route(Route::Left).average_delay(),
+        statistics.route(Route::Straight).average_delay(),
+        statistics.route(Route::Right).average_delay(),
+    );
+}
+
+fn run(args: &[String]) {
+    let scenario = parse_scenario(args);
+    let controller = parse_controller(args, &scenario);
+    let name = controller.name().to_string();
+    let simulation = parse_run(args, &scenario).run(controller).unwrap_or_else(|e| fail(e));
+    print_summary(&name, &simulation);
+}
+
+fn webster(args: &[String]) {
+    let scenario = parse_scenario(args);
+    let headless = parse_run(args, &scenario);
+    let controller = parse_controller(args, &scenario);
+    let name = controller.name().to_string();
+    let measured = headless.run(controller).unwrap_or_else(|e| fail(e));
+    print_summary(&name, &measured);
+
+    let flows = measured.measured_flows();
+    println!("\nmeasured flows (veh/h):");
+    for origin in Origin::ALL {
+        let [left, straight, right] = Route::ALL.map(|route| flows[&Movement::new(origin, route)] * 3600.0);
+        println!("  {:<6} left {:>5.0}  straight {:>5.0}  right {:>5.0}", format!("{:?}", origin), left, straight, right);
+    }
+
+    // Each approach is one shared lane, so split phasing is the natural
+    // stage design: every phase discharges one lane completely.
+    let phases: Vec<Phase> = Origin::ALL.into_iter().map(Phase::approach).collect();
+    let params = WebsterParams::from_config(&headless.config);
+    let timing = WebsterTiming::compute(&phases, &flows, &params).unwrap_or_else(|e| fail(e));
+
+    println!("\nwebster timing:");
+    for phase in &timing.phases {
+        println!("  {:<6} y = {:.3}  green {:.1}s", phase.phase.name, phase.flow_ratio, phase.green);
+    }
+    println!(
+        "  lost time L = {:.1}s, Y = {:.3}, C0 = {:.1}s, cycle {:.1}s",
+        timing.lost_time, timing.total_ratio, timing.optimal_cycle, timing.cycle
+    );
+    if timing.is_oversaturated() {
+        println!("  Y >= 1: demand exceeds capacity, cycle capped at {:.0}s", params.max_cycle);
+    }
+
+    let plan = timing.to_plan(format!("webster {:.0}s", timing.cycle));
+    let out = option(args, "--out").unwrap_or("webster.toml");
+    fs::write(out, plan.to_toml()).unwrap_or_else(|e| fail(format!("{}: {}", out, e)));
+    println!("\nwrote {}", out);
+
+    let replay = headless.run(Box::new(FixedTimeController::new(plan))).unwrap_or_else(|e| fail(e));
+    print_summary("webster plan", &replay);
+}
+
+fn parse_target(args: &[String], config: &SimConfig) -> TimingTarget {
+    match option(args, "--target").unwrap_or("plan") {
+        "plan" => {
+            let mut plan = match option(args, "--plan") {
+                Some(path) => SignalPlan::load_with(path, config).unwrap_or_else(|e| fail(format!("{}: {}", path, e))),
+                None => SignalPlan::four_phase(10.0, config),
+            };
+            plan.name = format!("{} (optimized)", plan.name);
+            TimingTarget::Plan(plan, WebsterParams::from_config(config))
+        }
+        "demand" => TimingTarget::Demand(DemandParams::from_config(config)),
+        "actuated" => TimingTarget::Actuated(ActuatedParams::from_config(config)),
+        other => fail(format!("unknown target '{}'", other)),
+    }
+}
+
+fn optimize(args: &[String]) {
+    let headless = parse_run(args, &parse_scenario(args));
+    let target = parse_target(args, &headless.config);
+    let objective = match option(args, "--objective").unwrap_or("delay") {
+        "delay" => Objective::Delay,
+        "throughput" => Objective::Throughput,
+        other => fail(format!("unknown objective '{}'", other)),
+    };
+    let seeds: u64 = number(args, "--seeds").unwrap_or(2);
+
+    let mut params = GeneticParams {
+        seed: headless.seed,
+        ..GeneticParams::default()
+    };
+    if let Some(population) = number(args, "--population") {
+        params.population = population;
+    }
+    if let Some(generations) = number(args, "--generations") {
+        params.generations = generations;
+    }
+    if let Some(threads) = number(args, "--threads") {
+        params.threads = threads;
+    }
+
+    // Timings never change the phases, so checking the template once covers
+    // every candidate.
+    let initial: Vec<f32> = target.genes().iter().map(|g| g.initial).collect();
+    let conflicts = ConflictMatrix::from_geometry(&headless.config);
+    target.controller(&initial).validate(&conflicts).unwrap_or_else(|e| fail(e));
+
+    // Every candidate sees the same seeds, so they are compared on identical demand.
+    let cost = |genes: &[f32]| {
+        let total: f32 = (0..seeds.max(1))
+            .map(|i| {
+                let run = HeadlessRun {
+                    seed: headless.seed + i,
+                    ..headless.clone()
+                };
+                objective.cost(&run.run(target.controller(genes)).expect("the template plan was validated"))
+            })
+            .sum();
+        total / seeds.max(1) as f32
+    };
+
+    let search = GeneticSearch::new(target.genes(), params);
+    let names: Vec<&str> = search.genes().iter().map(|g| g.name.as_str()).collect();
+    let mut log = format!("generation,best,mean,{}\n", names.join(","));
+
+    let (best, best_cost) = search.run(cost, |generation| {
+        let genes: Vec<String> = generation.best_genes.iter().map(|g| format!("{:.3}", g)).collect();
+        println!(
+            "generation {:>3}: best {:.3}  mean {:.3}  [{}]",
+            generation.index,
+            generation.best,
+            generation.mean,
+            genes.join(", ")
+        );
+        log.push_str(&format!("{},{},{},{}\n", generation.index, generation.best, generation.mean, genes.join(",")));
+    });
+
+    let log_path = option(args, "--log").unwrap_or("convergence.csv");
+    fs::write(log_path, log).unwrap_or_else(|e| fail(format!("{}: {}", log_path, e)));
+
+    let out = option(args, "--out").unwrap_or("optimized.toml");
+    fs::write(out, target.to_toml(&best)).unwrap_or_else(|e| fail(format!("{}: {}", out, e)));
+
+    println!("\nstarting point cost {:.3}, best {:.3}", cost(&initial), best_cost);
+    println!("wrote {} and {}", out, log_path);
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        Some("run") => run(&args[1..]),
+        Some("webster") => webster(&args[1..]),
+        Some("optimize") => optimize(&args[1..]),
+        _ => fail(USAGE),
+    }
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::app_config::*;
+use crate::control::controller::{Observation, SignalController};
+use crate::control::phase::Phase;
+use crate::control::plan::seconds;
+use crate::entity::detector::DetectorKind;
+use crate::types::{Movement, Origin, Route};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ActuatedParams {
+    #[serde(serialize_with = "seconds")]
+    pub min_green: f32,
+    #[serde(serialize_with = "seconds")]
+    pub max_green: f32,
+    // Each actuation extends the green by this much; once every detector on
+    // the green approaches has been idle for longer, the phase gaps out.
+    #[serde(serialize_with = "seconds")]
+    pub passage_time: f32,
+}
+
+impl Default for ActuatedParams {
+    fn default() -> Self {
+        Self::from_config(&SimConfig::default())
+    }
+}
+
+impl ActuatedParams {
+    pub fn from_config(config: &SimConfig) -> Self {
+        Self {
+            min_green: config.controller.actuated_min_green,
+            max_green: config.controller.max_green,
+            passage_time: config.controller.passage_time,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let positive = [("min_green", self.min_green), ("passage_time", self.passage_time)];
+        if let Some((name, value)) = positive.iter().find(|(_, v)| !(v.is_finite() && *v > 0.0)) {
+            return Err(format!("{} must be positive, got {}", name, value));
+        }
+        if !(self.max_green.is_finite() && self.max_green >= self.min_green) {
+            return Err(format!("max_green {}s is shorter than min_green {}s", self.max_green, self.min_green));
+        }
+        Ok(())
+    }
+}
+
+// Sees the intersection only through its detectors, like a real cabinet.
+#[derive(Debug, Clone, Default)]
+pub struct ActuatedController {
+    params: ActuatedParams,
+    // Approaches that had a vehicle detected since they last had the green.
+    calls: HashSet<Origin>,
+    seen_actuations: Vec<usize>,
+}
+
+impl ActuatedController {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_params(params: ActuatedParams) -> Self {
+        Self {
+            params,
+            ..Self::default()
+        }
+    }
+
+    pub fn params(&self) -> &ActuatedParams {
+        &self.params
+    }
+
+    fn register_calls(&mut self, observation: &Observation) {
+        self.seen_actuations.resize(observation.detectors.len(), 0);
+
+        for (detector, seen) in observation.detectors.iter().zip(&mut self.seen_actuations) {
+            let waiting = detector.kind == DetectorKind::Presence && detector.occupied();
+            if detector.actuations() > *seen || waiting {
+                self.calls.insert(detector.origin);
+            }
+            *seen = detector.actuations();
+        }
+    }
+
+    fn has_call(&self, phase: &Phase) -> bool {
+        phase.movements.iter().any(|m| self.calls.contains(&m.origin))
+    }
+
+    // The next phase in sequence after `current` that answers a call.
+    fn next_phase<'a>(&self, observation: &Observation<'a>, current: Option<&Phase>) -> Option<&'a Phase> {
+        let phases = observation.phases;
+        let start = current
+            .and_then(|c| phases.iter().position(|p| p.same_movements(c)))
+            .map_or(0, |i| i + 1);
+
+        (0..phases.len())
+            .map(|i| &phases[(start + i) % phases.len()])
+            .find(|p| current.is_none_or(|c| !p.same_movements(c)) && self.has_call(p))
+    }
+}
+
+impl SignalController for ActuatedController {
+    fn name(&self) -> &str {
+        "actuated"
+    }
+
+    fn decide(&mut self, observation: &Observation) -> Option<Phase> {
+        self.register_calls(observation);
+
+        let Some(current) = observation.active_phase else {
+            return self.next_phase(observation, None).or(observation.phases.first()).cloned();
+        };
+
+        // A lane detector cannot tell turning intent apart, so a call is only
+        // answered once every movement of its approach has run protected.
+        self.calls
+            .retain(|&o| !Route::ALL.into_iter().all(|r| current.is_protected(Movement::new(o, r))));
+
+        if observation.green_elapsed < self.params.min_green {
+            return Some(current.clone());
+        }
+
+        // Without a conflicting call the green rests where it is.
+        let Some(next) = self.next_phase(observation, Some(current)) else {
+            return Some(current.clone());
+        };
+
+        let gapped_out = observation
+            .detectors
+            .iter()
+            .filter(|d| current.serves_origin(d.origin))
+            .all(|d| d.gap() >= self.params.passage_time);
+        let maxed_out = observation.green_elapsed >= self.params.max_green;
+
+        if gapped_out || maxed_out {
+            Some(next.clone())
+        } else {
+            Some(current.clone())
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use crate::control::phase::{ConflictMatrix, Phase};
+use crate::control::plan::PlanError;
+use crate::entity::vehicle::Vehicle;
+use crate::entity::detector::Detector;
+use crate::entity::path::travel_direction;
+use crate::geometry::dot;
+use crate::types::{Movement, Origin};
+
+pub struct Observation<'a> {
+    pub time: f32,
+    pub vehicles: &'a [Vehicle],
+    pub phases: &'a [Phase],
+    pub detectors: &'a [Detector],
+    pub queues: HashMap<Movement, usize>,
+    pub oldest_wait: HashMap<Movement, f32>,
+    pub active_phase: Option<&'a Phase>,
+    pub green_elapsed: f32,
+    pub center_empty: bool,
+    pub center: (f32, f32),
+}
+
+pub trait SignalController: Send {
+    fn name(&self) -> &str;
+
+    // Called every tick; returns the phase that should have the green.
+    fn decide(&mut self, observation: &Observation) -> Option<Phase>;
+
+    // Amber and all-red times for ending the green on `ending`; `None` keeps
+    // the signal's own clearance times.
+    fn clearance(&self, _ending: &Phase) -> Option<(f32, f32)> {
+        None
+    }
+
+    // Checks the phases the controller will ask for against the signal's
+    // conflicts before it is installed.
+    fn validate(&self, _conflicts: &ConflictMatrix) -> Result<(), PlanError> {
+        Ok(())
+    }
+}
+
+pub fn queue_lengths(vehicles: &[Vehicle]) -> HashMap<Movement, usize> {
+    Movement::all()
+        .map(|m| (m, vehicles.iter().filter(|v| v.movement() == m && !v.turned).count()))
+        .collect()
+}
+
+pub fn oldest_waits(vehicles: &[Vehicle]) -> HashMap<Movement, f32> {
+    Movement::all()
+        .map(|m| {
+            let oldest = vehicles
+                .iter()
+                .filter(|v| v.movement() == m && !v.turned)
+                .map(|v| v.wait_time)
+                .fold(0.0, f32::max);
+            (m, oldest)
+        })
+        .collect()
+}
+
+// Vehicles still approaching on `origin`'s lane, front of the queue first.
+pub fn lane_queue(vehicles: &[Vehicle], origin: Origin) -> Vec<&Vehicle> {
+    let dir = travel_direction(origin);
+    let mut queue: Vec<&Vehicle> = vehicles.iter().filter(|v| v.origin == origin && !v.turned).collect();
+    queue.sort_by(|a, b| dot(b.pos, dir).total_cmp(&dot(a.pos, dir)));
+    queue
+}
@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::app_config::*;
+use crate::control::controller::{lane_queue, Observation, SignalController};
+use crate::control::phase::Phase;
+use crate::control::plan::{seconds, seconds_per_origin};
+use crate::entity::vehicle::Vehicle;
+use crate::types::{Movement, Origin};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DemandParams {
+    #[serde(serialize_with = "seconds")]
+    pub min_green: f32,
+    #[serde(serialize_with = "seconds_per_origin")]
+    pub max_green: HashMap<Origin, f32>,
+    #[serde(serialize_with = "seconds")]
+    pub max_wait: f32,
+    pub wait_weight: f32,
+}
+
+impl Default for DemandParams {
+    fn default() -> Self {
+        Self::from_config(&SimConfig::default())
+    }
+}
+
+impl DemandParams {
+    pub fn from_config(config: &SimConfig) -> Self {
+        let defaults = &config.controller;
+        Self {
+            min_green: defaults.min_green,
+            max_green: Origin::ALL
+                .into_iter()
+                .map(|o| (o, defaults.max_green))
+                .collect(),
+            max_wait: defaults.max_wait,
+            wait_weight: defaults.wait_weight,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let positive = [("min_green", self.min_green), ("max_wait", self.max_wait)];
+        if let Some((name, value)) = positive.iter().find(|(_, v)| !(v.is_finite() && *v > 0.0)) {
+            return Err(format!("{} must be positive, got {}", name, value));
+        }
+        for origin in Origin::ALL {
+            match self.max_green.get(&origin) {
+                None => return Err(format!("max_green has no value for {:?}", origin)),
+                Some(max) if !(max.is_finite() && *max >= self.min_green) => {
+                    return Err(format!("max_green {}s for {:?} is shorter than min_green {}s", max, origin, self.min_green));
+                }
+                Some(_) => {}
+            }
+        }
+        if !self.wait_weight.is_finite() || self.wait_weight < 0.0 {
+            return Err("wait_weight must be a finite, non-negative number".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DemandController {
+    params: DemandParams,
+}
+
+impl DemandController {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_params(params: DemandParams) -> Self {
+        Self { params }
+    }
+
+    pub fn params(&self) -> &DemandParams {
+        &self.params
+    }
+
+    // Lanes are shared by all movements of an approach, so a phase can only
+    // release the vehicles ahead of the first one it keeps red. Each lane
+    // scores those, boosted by how long the oldest of them has been waiting.
+    fn score(&self, observation: &Observation, phase: &Phase) -> f32 {
+        Origin::ALL
+            .into_iter()
+            .map(|origin| {
+                let released: Vec<&Vehicle> = lane_queue(observation.vehicles, origin)
+                    .into_iter()
+                    .take_while(|v| phase.is_protected(v.movement()))
+                    .collect();
+                if released.is_empty() {
+                    return 0.0;
+                }
+                let oldest = released.iter().map(|v| v.wait_time).fold(0.0, f32::max);
+                released.len() as f32 + self.params.wait_weight * oldest
+            })
+            .sum()
+    }
+
+    fn best_phase<'a>(&self, observation: &Observation<'a>, exclude: Option<&Phase>) -> Option<&'a Phase> {
+        let mut best_phase = None;
+        let mut best_score = -1.0;
+
+        for phase in observation.phases {
+            if exclude.is_some_and(|e| e.same_movements(phase)) {
+                continue;
+            }
+
+            let score = self.score(observation, phase);
+
+            if score > best_score && score > 0.0 {
+                best_score = score;
+                best_phase = Some(phase);
+            }
+        }
+
+        best_phase
+    }
+
+    // The best phase protecting the longest-starved movement the current phase does not.
+    fn starved_phase<'a>(&self, observation: &Observation<'a>, current: &Phase) -> Option<&'a Phase> {
+        let starved = Movement::all()
+            .filter(|&m| !current.is_protected(m) && observation.queues[&m] > 0)
+            .filter(|m| observation.oldest_wait[m] >= self.params.max_wait)
+            .max_by(|a, b| observation.oldest_wait[a].total_cmp(&observation.oldest_wait[b]))?;
+
+        observation
+            .phases
+            .iter()
+            .filter(|p| p.is_protected(starved))
+            .max_by(|a, b| self.score(observation, a).total_cmp(&self.score(observation, b)))
+    }
+
+    // A phase may run as long as the shortest max green among the approaches
+    // it serves. Valid parameters have one for every approach.
+    fn max_green(&self, phase: &Phase) -> f32 {
+        Origin::ALL
+            .into_iter()
+            .filter(|&o| phase.serves_origin(o))
+            .filter_map(|o| self.params.max_green.get(&o).copied())
+            .fold(f32::MAX, f32::min)
+    }
+}
+
+impl SignalController for DemandController {
+    fn name(&self) -> &str {
+        "demand"
+    }
+
+    fn decide(&mut self, observation: &Observation) -> Option<Phase> {
+        let Some(current) = observation.active_phase else {
+            return self
+                .best_phase(observation, None)
+                .or(observation.phases.first())
+                .cloned();
+        };
+
+        if observation.green_elapsed < self.params.min_green {
+            return Some(current.clone());
+        }
+
+        // Starvation and max-green protection end the green even while the
+        // box is occupied; the clearance intervals empty it before the switch.
+        if let Some(phase) = self.starved_phase(observation, current) {
+            return Some(phase.clone());
+        }
+
+        if observation.green_elapsed >= self.max_green(current)
+            && let Some(phase) = self.best_phase(observation, Some(current))
+        {
+            return Some(phase.clone());
+        }
+
+        // Ties keep the current phase rather than paying for a clearance.
+        match self.best_phase(observation, None) {
+            Some(best) if observation.center_empty && self.score(observation, best) > self.score(observation, current) => {
+                Some(best.clone())
+            }
+            _ => Some(current.clone()),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use crate::control::controller::{Observation, SignalController};
+use crate::control::plan::{PlanError, SignalPlan};
+use crate::control::phase::{ConflictMatrix, Phase};
+
+pub struct FixedTimeController {
+    plan: SignalPlan,
+    ending_clearance: Option<(f32, f32)>,
+}
+
+impl FixedTimeController {
+    pub fn new(plan: SignalPlan) -> Self {
+        Self {
+            plan,
+            ending_clearance: None,
+        }
+    }
+
+    pub fn plan(&self) -> &SignalPlan {
+        &self.plan
+    }
+}
+
+impl SignalController for FixedTimeController {
+    fn name(&self) -> &str {
+        &self.plan.name
+    }
+
+    // Once a phase's green is over we already ask for the next phase, so the
+    // signal's amber and all-red run inside the plan's clearance window.
+    fn decide(&mut self, observation: &Observation) -> Option<Phase> {
+        let (index, into_phase) = self.plan.phase_at(observation.time);
+        let phase = &self.plan.phases[index];
+
+        if into_phase < phase.green {
+            Some(phase.phase())
+        } else {
+            self.ending_clearance = Some((phase.amber, phase.all_red));
+            let next = (index + 1) % self.plan.phases.len();
+            Some(self.plan.phases[next].phase())
+        }
+    }
+
+    fn clearance(&self, _ending: &Phase) -> Option<(f32, f32)> {
+        self.ending_clearance
+    }
+
+    fn validate(&self, conflicts: &ConflictMatrix) -> Result<(), PlanError> {
+        self.plan.validate_against(conflicts)
+    }
+}
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+
+use crate::app_config::*;
+use crate::control::controller::{Observation, SignalController};
+use crate::control::phase::Phase;
+use crate::control::plan::seconds;
+use crate::entity::path::exit_direction;
+use crate::geometry::{dot, sub};
+use crate::types::Movement;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MaxPressureParams {
+    // A phase runs at least this long before the pressures are compared again.
+    #[serde(serialize_with = "seconds")]
+    pub min_phase: f32,
+}
+
+impl Default for MaxPressureParams {
+    fn default() -> Self {
+        Self::from_config(&SimConfig::default())
+    }
+}
+
+impl MaxPressureParams {
+    pub fn from_config(config: &SimConfig) -> Self {
+        Self {
+            min_phase: config.controller.max_pressure_min_phase,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.min_phase.is_finite() && self.min_phase > 0.0) {
+            return Err(format!("min_phase must be positive, got {}", self.min_phase));
+        }
+        Ok(())
+    }
+}
+
+// Max-pressure (back-pressure) control: a movement's pressure is its upstream
+// queue minus the vehicles already on the road it leaves by, and the phase
+// whose protected movements have the highest total pressure gets the green.
+#[derive(Debug, Clone, Default)]
+pub struct MaxPressureController {
+    params: MaxPressureParams,
+}
+
+impl MaxPressureController {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_params(params: MaxPressureParams) -> Self {
+        Self { params }
+    }
+
+    pub fn params(&self) -> &MaxPressureParams {
+        &self.params
+    }
+
+    // Vehicles on the exit leg the movement discharges into, up to one lane
+    // length past the box; traffic further away has left the link.
+    fn downstream(observation: &Observation, movement: Movement) -> usize {
+        let exit = exit_direction(movement.origin, movement.route);
+        observation
+            .vehicles
+            .iter()
+            .filter(|v| {
+                let past_box = dot(sub(v.pos, observation.center), exit) - v.config.road_width / 2.0;
+                v.turned
+                    && dot(exit_direction(v.origin, v.route), exit) > 0.5
+                    && (0.0..=v.config.lane_length).contains(&past_box)
+            })
+            .count()
+    }
+
+    pub fn pressure(observation: &Observation, phase: &Phase) -> f32 {
+        phase
+            .movements
+            .iter()
+            .map(|&m| observation.queues[&m] as f32 - Self::downstream(observation, m) as f32)
+            .sum()
+    }
+
+    fn max_pressure_phase<'a>(observation: &Observation<'a>) -> Option<&'a Phase> {
+        observation
+            .phases
+            .iter()
+            .max_by(|a, b| Self::pressure(observation, a).total_cmp(&Self::pressure(observation, b)))
+    }
+}
+
+impl SignalController for MaxPressureController {
+    fn name(&self) -> &str {
+        "max-pressure"
+    }
+
+    fn decide(&mut self, observation: &Observation) -> Option<Phase> {
+        let Some(current) = observation.active_phase else {
+            return Self::max_pressure_phase(observation).cloned();
+        };
+
+        if observation.green_elapsed < self.params.min_phase {
+            return Some(current.clone());
+        }
+
+        // Ties keep the current phase rather than paying for a clearance.
+        match Self::max_pressure_phase(observation) {
+            Some(best) if Self::pressure(observation, best) > Self::pressure(observation, current) => {
+                Some(best.clone())
+            }
+            _ => Some(current.clone()),
+        }
+    }
+}
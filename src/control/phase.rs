@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::app_config::SimConfig;
+use crate::entity::path::movement_path;
+use crate::geometry::{add, dot, right_normal, scale, sub};
+use crate::types::{Movement, Origin, Route, SignalAspect, VehicleClass};
+
+// A set of movements that are shown green together. `movements` run
+// protected; `permissive` ones may proceed but yield to conflicting traffic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Phase {
+    pub name: String,
+    pub movements: Vec<Movement>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissive: Vec<Movement>,
+}
+
+impl Phase {
+    pub fn new(name: impl Into<String>, movements: Vec<Movement>) -> Self {
+        Self {
+            name: name.into(),
+            movements,
+            permissive: Vec::new(),
+        }
+    }
+
+    pub fn with_permissive(mut self, permissive: Vec<Movement>) -> Self {
+        self.permissive = permissive.into_iter().filter(|m| !self.movements.contains(m)).collect();
+        self
+    }
+
+    // Every movement of one approach, i.e. a classic split phase.
+    pub fn approach(origin: Origin) -> Self {
+        let movements = Route::ALL.into_iter().map(|route| Movement::new(origin, route)).collect();
+        Self::new(format!("{:?}", origin), movements)
+    }
+
+    pub fn is_protected(&self, movement: Movement) -> bool {
+        self.movements.contains(&movement)
+    }
+
+    pub fn serves(&self, movement: Movement) -> bool {
+        self.movements.contains(&movement) || self.permissive.contains(&movement)
+    }
+
+    pub fn serves_origin(&self, origin: Origin) -> bool {
+        self.movements.iter().chain(&self.permissive).any(|m| m.origin == origin)
+    }
+
+    pub fn aspect(&self, movement: Movement) -> SignalAspect {
+        if self.is_protected(movement) {
+            SignalAspect::Green
+        } else if self.permissive.contains(&movement) {
+            SignalAspect::Permissive
+        } else {
+            SignalAspect::Red
+        }
+    }
+
+    pub fn same_movements(&self, other: &Phase) -> bool {
+        Movement::all().all(|m| self.aspect(m) == other.aspect(m))
+    }
+}
+
+// The stretch of the first movement's path, measured from its stop line,
+// that comes closer than `conflict_distance` to the second one's. `enter` is
+// where the front bumper of the widest vehicle would get that close, `exit`
+// where the centerline leaves it again.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConflictZone {
+    pub enter: f32,
+    pub exit: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConflictMatrix {
+    zones: HashMap<(Movement, Movement), ConflictZone>,
+}
+
+impl Default for ConflictMatrix {
+    fn default() -> Self {
+        Self::from_geometry(&SimConfig::default())
+    }
+}
+
+impl ConflictMatrix {
+    // Two movements conflict when their paths through the box cross or merge,
+    // i.e. come closer than a vehicle's width. Movements from the same
+    // approach share a lane and never conflict. The layout is symmetric, so
+    // the matrix is the same wherever the intersection is drawn.
+    pub fn from_geometry(config: &SimConfig) -> Self {
+        let paths: Vec<(Movement, Vec<(f32, f32)>)> = Movement::all()
+            .map(|m| (m, movement_path(m.origin, m.route, (0.0, 0.0), 2.0, config)))
+            .collect();
+
+        let half_width = VehicleClass::ALL
+            .iter()
+            .map(|c| c.spec(config).width / 2.0)
+            .fold(0.0, f32::max);
+
+        let mut zones = HashMap::new();
+        for (a, path_a) in &paths {
+            for (b, path_b) in &paths {
+                if a.origin == b.origin {
+                    continue;
+                }
+
+                let near = |p: (f32, f32)| {
+                    path_b.iter().any(|&q| {
+                        let d = sub(p, q);
+                        dot(d, d) < config.conflict_distance * config.conflict_distance
+                    })
+                };
+
+                let mut travelled = 0.0;
+                let mut enter: Option<f32> = None;
+                let mut exit: Option<f32> = None;
+                for i in 1..path_a.len() {
+                    let step = sub(path_a[i], path_a[i - 1]);
+                    let length = dot(step, step).sqrt();
+                    travelled += length;
+
+                    let p = path_a[i];
+                    let across = scale(right_normal(scale(step, 1.0 / length)), half_width);
+                    if enter.is_none() && [p, add(p, across), sub(p, across)].into_iter().any(near) {
+                        enter = Some(travelled);
+                    }
+                    if near(p) {
+                        exit = Some(travelled);
+                    }
+                }
+
+                let zone = enter.map(|enter| ConflictZone {
+                    enter,
+                    exit: exit.unwrap_or(enter),
+                });
+
+                if let Some(zone) = zone {
+                    zones.insert((*a, *b), zone);
+                }
+            }
+        }
+
+        Self { zones }
+    }
+
+    pub fn conflicts(&self, a: Movement, b: Movement) -> bool {
+        self.zones.contains_key(&(a, b))
+    }
+
+    // The part of `a`'s path it shares with `b`.
+    pub fn zone(&self, a: Movement, b: Movement) -> Option<ConflictZone> {
+        self.zones.get(&(a, b)).copied()
+    }
+
+    pub fn first_conflict(&self, movements: &[Movement]) -> Option<(Movement, Movement)> {
+        movements.iter().enumerate().find_map(|(i, &a)| {
+            movements[i + 1..]
+                .iter()
+                .find(|&&b| self.conflicts(a, b))
+                .map(|&b| (a, b))
+        })
+    }
+
+    pub fn is_compatible(&self, movements: &[Movement]) -> bool {
+        self.first_conflict(movements).is_none()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RingPhase {
+    pub phase: Phase,
+    pub barrier: usize,
+}
+
+// NEMA-style dual ring: each ring runs its phases in order, and the two rings
+// cross a barrier together, so only phases in the same barrier group can be
+// paired.
+#[derive(Debug, Clone)]
+pub struct DualRing {
+    pub rings: [Vec<RingPhase>; 2],
+}
+
+impl Default for DualRing {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl DualRing {
+    // Lead-lag lefts on both streets: the opposing left turns cross each
+    // other's path in this layout, so they never run together protected.
+    // Outside its arrow, each left runs permissive with its own through.
+    pub fn standard() -> Self {
+        let left = |origin: Origin, barrier| RingPhase {
+            phase: Phase::new(format!("{:?} left", origin), vec![Movement::new(origin, Route::Left)]),
+            barrier,
+        };
+        let through = |origin: Origin, barrier| RingPhase {
+            phase: Phase::new(
+                format!("{:?} through", origin),
+                vec![
+                    Movement::new(origin, Route::Straight),
+                    Movement::new(origin, Route::Right),
+                ],
+            )
+            .with_permissive(vec![Movement::new(origin, Route::Left)]),
+            barrier,
+        };
+
+        Self {
+            rings: [
+                vec![
+                    left(Origin::South, 0),
+                    through(Origin::North, 0),
+                    left(Origin::East, 1),
+                    through(Origin::West, 1),
+                ],
+                vec![
+                    through(Origin::South, 0),
+                    left(Origin::North, 0),
+                    through(Origin::East, 1),
+                    left(Origin::West, 1),
+                ],
+            ],
+        }
+    }
+
+    // Every compatible pairing of a ring 1 and a ring 2 phase within each
+    // barrier group, in ring order.
+    pub fn stages(&self, conflicts: &ConflictMatrix) -> Vec<Phase> {
+        let barriers = self.rings.iter().flatten().map(|p| p.barrier).max().map_or(0, |b| b + 1);
+        let mut stages = Vec::new();
+
+        for barrier in 0..barriers {
+            for a in self.rings[0].iter().filter(|p| p.barrier == barrier) {
+                for b in self.rings[1].iter().filter(|p| p.barrier == barrier) {
+                    let mut movements = a.phase.movements.clone();
+                    movements.extend(b.phase.movements.iter().filter(|&&m| !a.phase.is_protected(m)));
+                    let permissive = a.phase.permissive.iter().chain(&b.phase.permissive).copied().collect();
+
+                    if conflicts.is_compatible(&movements) {
+                        let name = format!("{} + {}", a.phase.name, b.phase.name);
+                        stages.push(Phase::new(name, movements).with_permissive(permissive));
+                    }
+                }
+            }
+        }
+
+        stages
+    }
+}
@@ -0,0 +1,268 @@
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::app_config::*;
+use crate::control::phase::{ConflictMatrix, Phase};
+use crate::types::{Movement, Origin};
+
+// A phase lists whole approaches, single movements, or both.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanPhase {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approaches: Vec<Origin>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub movements: Vec<Movement>,
+    // Movements that may go but must yield, e.g. lefts outside their arrow.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissive: Vec<Movement>,
+    #[serde(serialize_with = "seconds")]
+    pub green: f32,
+    #[serde(default = "default_amber", serialize_with = "seconds")]
+    pub amber: f32,
+    #[serde(default = "default_all_red", serialize_with = "seconds")]
+    pub all_red: f32,
+}
+
+impl PlanPhase {
+    pub fn phase(&self) -> Phase {
+        let mut movements: Vec<Movement> = self
+            .approaches
+            .iter()
+            .flat_map(|&origin| Phase::approach(origin).movements)
+            .collect();
+        for &m in &self.movements {
+            if !movements.contains(&m) {
+                movements.push(m);
+            }
+        }
+
+        let name = self.name.clone().unwrap_or_else(|| {
+            let mut parts: Vec<String> = self.approaches.iter().map(|o| format!("{:?}", o)).collect();
+            parts.extend(self.movements.iter().map(|m| format!("{:?} {:?}", m.origin, m.route)));
+            parts.join(" + ")
+        });
+
+        Phase::new(name, movements).with_permissive(self.permissive.clone())
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.green + self.amber + self.all_red
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalPlan {
+    pub name: String,
+    // Omitted cycles are derived from the phase durations.
+    #[serde(default, skip_serializing_if = "Option::is_none", serialize_with = "optional_seconds")]
+    pub cycle: Option<f32>,
+    #[serde(default, serialize_with = "seconds")]
+    pub offset: f32,
+    pub phases: Vec<PlanPhase>,
+}
+
+#[derive(Debug)]
+pub enum PlanError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanError::Io(e) => write!(f, "cannot read signal plan: {}", e),
+            PlanError::Parse(e) => write!(f, "cannot parse signal plan: {}", e),
+            PlanError::Invalid(msg) => write!(f, "invalid signal plan: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for PlanError {}
+
+impl SignalPlan {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PlanError> {
+        Self::load_with(path, &SimConfig::default())
+    }
+
+    pub fn load_with(path: impl AsRef<Path>, config: &SimConfig) -> Result<Self, PlanError> {
+        let text = fs::read_to_string(path).map_err(PlanError::Io)?;
+        Self::from_toml_with(&text, config)
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, PlanError> {
+        Self::from_toml_with(text, &SimConfig::default())
+    }
+
+    // Like `from_toml`, with clearance times the file leaves out taken from
+    // `config` and the plan checked against its layout.
+    pub fn from_toml_with(text: &str, config: &SimConfig) -> Result<Self, PlanError> {
+        let mut table: toml::Table = toml::from_str(text).map_err(PlanError::Parse)?;
+        fill_clearances(&mut table, config);
+        let plan: SignalPlan = table.try_into().map_err(PlanError::Parse)?;
+        plan.validate_against(&ConflictMatrix::from_geometry(config))?;
+        Ok(plan)
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).expect("signal plans always serialize")
+    }
+
+    // Checks against the default layout; a simulation with other geometry
+    // checks again with its own matrix when the plan is installed.
+    pub fn validate(&self) -> Result<(), PlanError> {
+        self.validate_against(&ConflictMatrix::default())
+    }
+
+    pub fn validate_against(&self, conflicts: &ConflictMatrix) -> Result<(), PlanError> {
+        if self.phases.is_empty() {
+            return Err(PlanError::Invalid(format!("plan '{}' has no phases", self.name)));
+        }
+
+        if !self.offset.is_finite() || self.cycle.is_some_and(|cycle| !cycle.is_finite()) {
+            return Err(PlanError::Invalid(format!("cycle and offset of '{}' must be finite", self.name)));
+        }
+
+        for (i, phase) in self.phases.iter().enumerate() {
+            let finite = phase.green.is_finite() && phase.amber.is_finite() && phase.all_red.is_finite();
+            if !finite || phase.green <= 0.0 || phase.amber < 0.0 || phase.all_red < 0.0 {
+                return Err(PlanError::Invalid(format!(
+                    "phase {} of '{}' needs a finite positive green and non-negative clearance times",
+                    i + 1,
+                    self.name
+                )));
+            }
+
+            let movements = phase.phase().movements;
+            if movements.is_empty() {
+                return Err(PlanError::Invalid(format!(
+                    "phase {} of '{}' has no approaches or movements",
+                    i + 1,
+                    self.name
+                )));
+            }
+
+            if let Some((a, b)) = conflicts.first_conflict(&movements) {
+                return Err(PlanError::Invalid(format!(
+                    "phase {} of '{}' runs conflicting movements {:?} {:?} and {:?} {:?}",
+                    i + 1,
+                    self.name,
+                    a.origin,
+                    a.route,
+                    b.origin,
+                    b.route
+                )));
+            }
+        }
+
+        let total: f32 = self.phases.iter().map(PlanPhase::duration).sum();
+        if let Some(cycle) = self.cycle
+            && (cycle - total).abs() > 0.01
+        {
+            return Err(PlanError::Invalid(format!(
+                "cycle of '{}' is {:.2}s but its phases add up to {:.2}s",
+                self.name, cycle, total
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn cycle_length(&self) -> f32 {
+        self.cycle
+            .unwrap_or_else(|| self.phases.iter().map(PlanPhase::duration).sum())
+    }
+
+    // Returns the index of the phase running at `time` and how far into it we are.
+    pub fn phase_at(&self, time: f32) -> (usize, f32) {
+        let mut t = (time - self.offset).rem_euclid(self.cycle_length());
+
+        for (i, phase) in self.phases.iter().enumerate() {
+            if t < phase.duration() {
+                return (i, t);
+            }
+            t -= phase.duration();
+        }
+
+        (self.phases.len() - 1, t)
+    }
+
+    // Clearance times follow the configuration.
+    pub fn four_phase(green: f32, config: &SimConfig) -> Self {
+        let phase = |approach| PlanPhase {
+            name: None,
+            approaches: vec![approach],
+            movements: Vec::new(),
+            permissive: Vec::new(),
+            green,
+            amber: config.timing.amber_time,
+            all_red: config.timing.all_red_time,
+        };
+
+        Self {
+            name: format!("four-phase {:.0}s", green),
+            cycle: None,
+            offset: 0.0,
+            phases: vec![
+                phase(Origin::North),
+                phase(Origin::East),
+                phase(Origin::South),
+                phase(Origin::West),
+            ],
+        }
+    }
+}
+
+fn default_amber() -> f32 {
+    SimConfig::default().timing.amber_time
+}
+
+fn default_all_red() -> f32 {
+    SimConfig::default().timing.all_red_time
+}
+
+// Gives the phases of a plan table the configuration's clearance times
+// where they leave them out.
+pub(crate) fn fill_clearances(table: &mut toml::Table, config: &SimConfig) {
+    let Some(toml::Value::Array(phases)) = table.get_mut("phases") else {
+        return;
+    };
+    for phase in phases.iter_mut().filter_map(|p| p.as_table_mut()) {
+        phase
+            .entry("amber")
+            .or_insert(toml::Value::Float(f64::from(config.timing.amber_time)));
+        phase
+            .entry("all_red")
+            .or_insert(toml::Value::Float(f64::from(config.timing.all_red_time)));
+    }
+}
+
+// Durations are written rounded to the millisecond so that f32 values don't
+// show up as long f64 expansions in the file.
+pub(crate) fn seconds<S: Serializer>(value: &f32, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64((f64::from(*value) * 1000.0).round() / 1000.0)
+}
+
+pub(crate) fn optional_seconds<S: Serializer>(value: &Option<f32>, serializer: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => seconds(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub(crate) fn seconds_per_origin<S: Serializer>(values: &HashMap<Origin, f32>, serializer: S) -> Result<S::Ok, S::Error> {
+    // In approach order, so files diff cleanly.
+    let mut map = serializer.serialize_map(Some(values.len()))?;
+    for origin in Origin::ALL {
+        if let Some(value) = values.get(&origin) {
+            map.serialize_entry(&origin, &((f64::from(*value) * 1000.0).round() / 1000.0))?;
+        }
+    }
+    map.end()
+}
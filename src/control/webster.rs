@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use crate::app_config::*;
+use crate::control::phase::Phase;
+use crate::control::plan::{PlanError, PlanPhase, SignalPlan};
+use crate::types::{Movement, Origin, Route};
+
+#[derive(Debug, Clone)]
+pub struct WebsterParams {
+    // Discharge rate of a saturated lane, vehicles per second of green.
+    pub saturation_flow: f32,
+    pub startup_lost: f32,
+    // Part of the amber that is still used by traffic.
+    pub amber_extension: f32,
+    pub amber: f32,
+    pub all_red: f32,
+    pub min_green: f32,
+    pub min_cycle: f32,
+    pub max_cycle: f32,
+}
+
+impl Default for WebsterParams {
+    fn default() -> Self {
+        Self::from_config(&SimConfig::default())
+    }
+}
+
+impl WebsterParams {
+    pub fn from_config(config: &SimConfig) -> Self {
+        let timing = &config.timing;
+        Self {
+            saturation_flow: timing.saturation_flow,
+            startup_lost: timing.startup_lost_time,
+            amber_extension: timing.amber_extension,
+            amber: timing.amber_time,
+            all_red: timing.all_red_time,
+            min_green: timing.webster_min_green,
+            min_cycle: timing.webster_min_cycle,
+            max_cycle: timing.webster_max_cycle,
+        }
+    }
+
+    pub fn lost_time_per_phase(&self) -> f32 {
+        (self.startup_lost + self.amber + self.all_red - self.amber_extension).max(0.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WebsterPhase {
+    pub phase: Phase,
+    // Flow ratio y = q / s of the phase's critical lane.
+    pub flow_ratio: f32,
+    pub green: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebsterTiming {
+    pub phases: Vec<WebsterPhase>,
+    pub lost_time: f32,
+    pub total_ratio: f32,
+    pub optimal_cycle: f32,
+    pub cycle: f32,
+    amber: f32,
+    all_red: f32,
+}
+
+impl WebsterTiming {
+    // Every approach is a single shared lane, so a phase's flow ratio for an
+    // approach is the flow of the movements it protects there over the
+    // lane's saturation flow, and its critical ratio the largest of those.
+    pub fn compute(phases: &[Phase], flows: &HashMap<Movement, f32>, params: &WebsterParams) -> Result<Self, PlanError> {
+        if phases.is_empty() {
+            return Err(PlanError::Invalid("Webster timing needs at least one phase".to_string()));
+        }
+
+        if let Some(m) = Movement::all().find(|&m| flows.get(&m).is_some_and(|&q| q > 0.0) && !phases.iter().any(|p| p.is_protected(m))) {
+            return Err(PlanError::Invalid(format!(
+                "{:?} {:?} has traffic but no phase protects it",
+                m.origin, m.route
+            )));
+        }
+
+        let flow_ratios: Vec<f32> = phases
+            .iter()
+            .map(|phase| {
+                Origin::ALL
+                    .into_iter()
+                    .map(|origin| {
+                        let flow: f32 = phase
+                            .movements
+                            .iter()
+                            .filter(|m| m.origin == origin)
+                            .map(|m| flows.get(m).copied().unwrap_or(0.0))
+                            .sum();
+                        flow / params.saturation_flow
+                    })
+                    .fold(0.0, f32::max)
+            })
+            .collect();
+
+        let lost_time = params.lost_time_per_phase() * phases.len() as f32;
+        let total_ratio: f32 = flow_ratios.iter().sum();
+
+        // C0 = (1.5 L + 5) / (1 - Y); it has no solution once Y reaches 1.
+        let optimal_cycle = if total_ratio < 1.0 {
+            (1.5 * lost_time + 5.0) / (1.0 - total_ratio)
+        } else {
+            f32::INFINITY
+        };
+        let target = optimal_cycle.clamp(params.min_cycle, params.max_cycle);
+
+        // Effective green is shared in proportion to the critical flow ratios.
+        let effective = (target - lost_time).max(0.0);
+        let phases: Vec<WebsterPhase> = phases
+            .iter()
+            .zip(&flow_ratios)
+            .map(|(phase, &flow_ratio)| {
+                let share = if total_ratio > 0.0 { flow_ratio / total_ratio } else { 1.0 / flow_ratios.len() as f32 };
+                let green = effective * share + params.startup_lost - params.amber_extension;
+                WebsterPhase {
+                    phase: phase.clone(),
+                    flow_ratio,
+                    // Tenths of a second are plenty for a signal controller.
+                    green: (green.max(params.min_green) * 10.0).round() / 10.0,
+                }
+            })
+            .collect();
+
+        let cycle = phases.iter().map(|p| p.green + params.amber + params.all_red).sum();
+
+        Ok(Self {
+            phases,
+            lost_time,
+            total_ratio,
+            optimal_cycle,
+            cycle,
+            amber: params.amber,
+            all_red: params.all_red,
+        })
+    }
+
+    pub fn is_oversaturated(&self) -> bool {
+        self.total_ratio >= 1.0
+    }
+
+    pub fn to_plan(&self, name: impl Into<String>) -> SignalPlan {
+        SignalPlan {
+            name: name.into(),
+            cycle: Some(self.cycle),
+            offset: 0.0,
+            phases: self
+                .phases
+                .iter()
+                .map(|p| {
+                    // Whole approaches are written as such to keep the file short.
+                    let approaches: Vec<Origin> = Origin::ALL
+                        .into_iter()
+                        .filter(|&o| Route::ALL.into_iter().all(|r| p.phase.is_protected(Movement::new(o, r))))
+                        .collect();
+                    PlanPhase {
+                        name: Some(p.phase.name.clone()),
+                        movements: p.phase.movements.iter().filter(|m| !approaches.contains(&m.origin)).copied().collect(),
+                        approaches,
+                        permissive: p.phase.permissive.clone(),
+                        green: p.green,
+                        amber: self.amber,
+                        all_red: self.all_red,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
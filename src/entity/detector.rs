@@ -0,0 +1,129 @@
+use macroquad::prelude::*;
+
+use crate::app_config::SimConfig;
+use crate::entity::path::{entry_point, heading_of, travel_direction};
+use crate::entity::vehicle::Vehicle;
+use crate::geometry::{scale, sub, Obb};
+use crate::render::palette::*;
+use crate::types::Origin;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectorKind {
+    // A long loop at the stop line that stays on while a vehicle waits on it.
+    Presence,
+    // A short upstream loop that pulses once per passing vehicle.
+    Passage,
+}
+
+// An inductive loop on one approach. `distance` is measured upstream from the
+// stop line to the loop's downstream edge.
+#[derive(Debug, Clone)]
+pub struct Detector {
+    pub origin: Origin,
+    pub kind: DetectorKind,
+    pub distance: f32,
+    pub length: f32,
+    occupied: bool,
+    actuations: usize,
+    gap: f32,
+    occupied_time: f32,
+    elapsed: f32,
+}
+
+impl Detector {
+    pub fn new(origin: Origin, kind: DetectorKind, distance: f32, length: f32) -> Self {
+        Self {
+            origin,
+            kind,
+            distance,
+            length,
+            occupied: false,
+            actuations: 0,
+            gap: 0.0,
+            occupied_time: 0.0,
+            elapsed: 0.0,
+        }
+    }
+
+    // A presence loop at the stop line and a passage loop upstream on every approach.
+    pub fn standard_layout(config: &SimConfig) -> Vec<Detector> {
+        Origin::ALL
+            .into_iter()
+            .flat_map(|origin| {
+                [
+                    Detector::new(origin, DetectorKind::Presence, 0.0, config.presence_length),
+                    Detector::new(origin, DetectorKind::Passage, config.passage_distance, config.passage_length),
+                ]
+            })
+            .collect()
+    }
+
+    pub fn update(&mut self, dt: f32, vehicles: &[Vehicle], center: (f32, f32)) {
+        let (near, far) = (self.distance, self.distance + self.length);
+        let occupied = vehicles.iter().any(|v| {
+            let front = v.distance_to_stop_line(center);
+            v.origin == self.origin && front < far && front + v.length > near
+        });
+
+        if occupied && !self.occupied {
+            self.actuations += 1;
+        }
+        self.occupied = occupied;
+
+        self.elapsed += dt;
+        if occupied {
+            self.occupied_time += dt;
+            self.gap = 0.0;
+        } else {
+            self.gap += dt;
+        }
+    }
+
+    pub fn occupied(&self) -> bool {
+        self.occupied
+    }
+
+    pub fn actuations(&self) -> usize {
+        self.actuations
+    }
+
+    // Seconds since a vehicle last left the loop; zero while it is occupied.
+    pub fn gap(&self) -> f32 {
+        self.gap
+    }
+
+    pub fn occupancy(&self) -> f32 {
+        if self.elapsed == 0.0 { 0.0 } else { self.occupied_time / self.elapsed }
+    }
+
+    pub fn draw(&self, center: (f32, f32), config: &SimConfig) {
+        let dir = travel_direction(self.origin);
+        let middle = sub(entry_point(self.origin, center, config), scale(dir, self.distance + self.length / 2.0));
+        let loop_area = Obb::new(middle, heading_of(dir), self.length, config.detector_width);
+
+        let color = match self.kind {
+            DetectorKind::Presence => COLOR_DETECTOR_PRESENCE,
+            DetectorKind::Passage => COLOR_DETECTOR_PASSAGE,
+        };
+
+        let corners = loop_area.corners();
+        if self.occupied {
+            draw_triangle(
+                vec2(corners[0].0, corners[0].1),
+                vec2(corners[1].0, corners[1].1),
+                vec2(corners[2].0, corners[2].1),
+                Color::new(color.r, color.g, color.b, 0.4),
+            );
+            draw_triangle(
+                vec2(corners[0].0, corners[0].1),
+                vec2(corners[2].0, corners[2].1),
+                vec2(corners[3].0, corners[3].1),
+                Color::new(color.r, color.g, color.b, 0.4),
+            );
+        }
+        for i in 0..4 {
+            let (a, b) = (corners[i], corners[(i + 1) % 4]);
+            draw_line(a.0, a.1, b.0, b.1, 1.5, color);
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::f32::consts::FRAC_PI_2;
+
+use crate::app_config::SimConfig;
+use crate::geometry::{add, dot, right_normal, scale, sub};
+use crate::types::{Origin, Route};
+
+#[derive(Debug, Clone, Copy)]
+pub struct TurnArc {
+    pub center: (f32, f32),
+    pub radius: f32,
+    pub entry_dir: (f32, f32),
+    pub exit_dir: (f32, f32),
+}
+
+impl TurnArc {
+    pub fn for_movement(origin: Origin, route: Route, center: (f32, f32), config: &SimConfig) -> Option<Self> {
+        let entry_dir = travel_direction(origin);
+        let exit_dir = match route {
+            Route::Straight => return None,
+            Route::Right => right_normal(entry_dir),
+            Route::Left => scale(right_normal(entry_dir), -1.0),
+        };
+
+        let entry_lane = lane_point(entry_dir, center, config);
+        let exit_lane = lane_point(exit_dir, center, config);
+
+        // The corner is where the entry and exit lane centerlines cross; the arc
+        // starts at the box edge and is tangent to both lanes.
+        let to_corner = dot(sub(exit_lane, entry_lane), entry_dir);
+        let corner = add(entry_lane, scale(entry_dir, to_corner));
+        let radius = config.road_width / 2.0 + to_corner;
+
+        Some(Self {
+            center: add(sub(corner, scale(entry_dir, radius)), scale(exit_dir, radius)),
+            radius,
+            entry_dir,
+            exit_dir,
+        })
+    }
+
+    pub fn length(&self) -> f32 {
+        self.radius * FRAC_PI_2
+    }
+
+    pub fn start(&self) -> (f32, f32) {
+        self.point_at(0.0)
+    }
+
+    pub fn end(&self) -> (f32, f32) {
+        self.point_at(self.length())
+    }
+
+    pub fn point_at(&self, distance: f32) -> (f32, f32) {
+        let theta = distance / self.radius;
+        add(
+            self.center,
+            add(
+                scale(self.exit_dir, -self.radius * theta.cos()),
+                scale(self.entry_dir, self.radius * theta.sin()),
+            ),
+        )
+    }
+
+    pub fn heading_at(&self, distance: f32) -> f32 {
+        let theta = distance / self.radius;
+        let dir = add(scale(self.entry_dir, theta.cos()), scale(self.exit_dir, theta.sin()));
+        dir.1.atan2(dir.0)
+    }
+
+    pub fn sample(&self, segments: usize) -> Vec<(f32, f32)> {
+        (0..=segments)
+            .map(|i| self.point_at(self.length() * i as f32 / segments as f32))
+            .collect()
+    }
+}
+
+pub fn travel_direction(origin: Origin) -> (f32, f32) {
+    match origin {
+        Origin::North => (0.0, 1.0),
+        Origin::South => (0.0, -1.0),
+        Origin::East => (1.0, 0.0),
+        Origin::West => (-1.0, 0.0),
+    }
+}
+
+// Direction of travel once a movement has left the box.
+pub fn exit_direction(origin: Origin, route: Route) -> (f32, f32) {
+    let dir = travel_direction(origin);
+    match route {
+        Route::Straight => dir,
+        Route::Right => right_normal(dir),
+        Route::Left => scale(right_normal(dir), -1.0),
+    }
+}
+
+// Centerline of a movement from the stop line to where it leaves the box,
+// sampled roughly every `spacing` units.
+pub fn movement_path(origin: Origin, route: Route, center: (f32, f32), spacing: f32, config: &SimConfig) -> Vec<(f32, f32)> {
+    if let Some(arc) = TurnArc::for_movement(origin, route, center, config) {
+        return arc.sample((arc.length() / spacing).ceil() as usize);
+    }
+
+    let dir = travel_direction(origin);
+    let entry = entry_point(origin, center, config);
+    let segments = (config.road_width / spacing).ceil() as usize;
+    (0..=segments)
+        .map(|i| add(entry, scale(dir, config.road_width * i as f32 / segments as f32)))
+        .collect()
+}
+
+// Where `origin`'s lane centerline crosses its stop line.
+pub fn entry_point(origin: Origin, center: (f32, f32), config: &SimConfig) -> (f32, f32) {
+    let dir = travel_direction(origin);
+    sub(lane_point(dir, center, config), scale(dir, config.road_width / 2.0))
+}
+
+pub fn heading_of(dir: (f32, f32)) -> f32 {
+    dir.1.atan2(dir.0)
+}
+
+fn lane_point(dir: (f32, f32), center: (f32, f32), config: &SimConfig) -> (f32, f32) {
+    add(center, scale(right_normal(dir), config.lane_center))
+}
@@ -1,105 +1,292 @@
 use macroquad::prelude::*;
-use std::collections::HashMap;
-use crate::types::Origin;
+use std::sync::Arc;
+use crate::types::{Movement, Origin, Route, SignalAspect};
+use crate::control::controller::{oldest_waits, queue_lengths, Observation, SignalController};
+use crate::control::demand_based::{DemandController, DemandParams};
+use crate::control::phase::{ConflictMatrix, DualRing, Phase};
+use crate::control::plan::PlanError;
+use crate::entity::detector::Detector;
+use crate::entity::path::exit_direction;
 use crate::entity::vehicle::Vehicle;
-use crate::app_config::*;
+use crate::geometry::{right_normal, scale, Obb};
+use crate::app_config::SimConfig;
 use crate::render::palette::*;
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignalInterval {
+    Green(Phase),
+    Amber(Phase),
+    AllRed,
+}
+
 pub struct TrafficLightSystem {
-    active_green: Option<Origin>,
-    green_timer: f32,
+    interval: SignalInterval,
+    interval_timer: f32,
+    pending_green: Option<Phase>,
+    // Movements served the same way by the ending and the pending phase keep
+    // their indication through the clearance intervals.
+    carried: Vec<(Movement, SignalAspect)>,
+    amber_time: f32,
+    all_red_time: f32,
+    clearance: (f32, f32),
+    phases: Vec<Phase>,
+    conflicts: ConflictMatrix,
+    detectors: Vec<Detector>,
+    controller: Box<dyn SignalController>,
+    config: Arc<SimConfig>,
 }
 
 impl TrafficLightSystem {
-    pub fn new() -> Self {
+    pub fn new(config: Arc<SimConfig>) -> Self {
+        let params = DemandParams::from_config(&config);
+        Self::with_controller(Box::new(DemandController::with_params(params)), config)
+    }
+
+    pub fn with_controller(controller: Box<dyn SignalController>, config: Arc<SimConfig>) -> Self {
+        let conflicts = ConflictMatrix::from_geometry(&config);
+
         Self {
-            active_green: None,
-            green_timer: 0.0,
+            interval: SignalInterval::AllRed,
+            interval_timer: config.timing.all_red_time,
+            pending_green: None,
+            carried: Vec::new(),
+            amber_time: config.timing.amber_time,
+            all_red_time: config.timing.all_red_time,
+            clearance: (config.timing.amber_time, config.timing.all_red_time),
+            phases: DualRing::standard().stages(&conflicts),
+            conflicts,
+            detectors: Detector::standard_layout(&config),
+            controller,
+            config,
         }
     }
 
-    pub fn update(&mut self, dt: f32, vehicles: &[Vehicle], center: (f32, f32)) {
-        self.green_timer += dt;
+    // A controller whose phases conflict in this layout is refused, and the
+    // current one keeps running.
+    pub fn set_controller(&mut self, controller: Box<dyn SignalController>) -> Result<(), PlanError> {
+        controller.validate(&self.conflicts)?;
+        self.controller = controller;
+        Ok(())
+    }
+
+    pub fn controller_name(&self) -> &str {
+        self.controller.name()
+    }
+
+    pub fn phases(&self) -> &[Phase] {
+        &self.phases
+    }
+
+    // Phases with conflicting movements are dropped.
+    pub fn set_phases(&mut self, phases: Vec<Phase>) {
+        self.phases = phases
+            .into_iter()
+            .filter(|p| self.conflicts.is_compatible(&p.movements))
+            .collect();
+    }
+
+    pub fn conflicts(&self) -> &ConflictMatrix {
+        &self.conflicts
+    }
+
+    pub fn detectors(&self) -> &[Detector] {
+        &self.detectors
+    }
+
+    pub fn set_detectors(&mut self, detectors: Vec<Detector>) {
+        self.detectors = detectors;
+    }
+
+    pub fn update(&mut self, dt: f32, time: f32, vehicles: &[Vehicle], center: (f32, f32)) {
+        self.interval_timer += dt;
+
+        for detector in &mut self.detectors {
+            detector.update(dt, vehicles, center);
+        }
+
+        match &self.interval {
+            SignalInterval::Green(current) => {
+                let current = current.clone();
+                let next = self.consult(time, vehicles, center);
+                if next.as_ref().is_some_and(|n| n.same_movements(&current)) {
+                    return;
+                }
+
+                // A phase that takes right of way from no movement needs no clearance.
+                if let Some(next) = next.as_ref()
+                    && Movement::all().all(|m| match current.aspect(m) {
+                        SignalAspect::Green => next.aspect(m) == SignalAspect::Green,
+                        SignalAspect::Permissive => next.serves(m),
+                        _ => true,
+                    })
+                {
+                    self.enter(SignalInterval::Green(next.clone()));
+                    return;
+                }
+
+                self.clearance = self
+                    .controller
+                    .clearance(&current)
+                    .unwrap_or((self.amber_time, self.all_red_time));
+                self.carried = match &next {
+                    Some(next) => Movement::all()
+                        .filter(|&m| current.serves(m) && current.aspect(m) == next.aspect(m))
+                        .map(|m| (m, current.aspect(m)))
+                        .collect(),
+                    None => Vec::new(),
+                };
+                self.pending_green = next;
+                self.enter(SignalInterval::Amber(current));
+            }
+            SignalInterval::Amber(_) => {
+                if self.interval_timer >= self.clearance.0 {
+                    self.enter(SignalInterval::AllRed);
+                }
+            }
+            SignalInterval::AllRed => {
+                if self.interval_timer >= self.clearance.1 {
+                    let next = match self.pending_green.take() {
+                        Some(phase) => Some(phase),
+                        None => self.consult(time, vehicles, center),
+                    };
+
+                    if let Some(phase) = next {
+                        self.carried.clear();
+                        self.enter(SignalInterval::Green(phase));
+                    }
+                }
+            }
+        }
+    }
+
+    fn enter(&mut self, interval: SignalInterval) {
+        self.interval = interval;
+        self.interval_timer = 0.0;
+    }
+
+    fn consult(&mut self, time: f32, vehicles: &[Vehicle], center: (f32, f32)) -> Option<Phase> {
+        let center_box = Obb::new(center, 0.0, self.config.center_half * 2.0, self.config.center_half * 2.0);
 
         let center_count = vehicles
             .iter()
             .filter(|v| {
-                (v.pos.0 - center.0).abs() < CENTER_HALF && (v.pos.1 - center.1).abs() < CENTER_HALF
+                // A permissive vehicle waiting for a gap does not hold the phase;
+                // it clears the box once conflicting traffic stops.
+                let waiting = v.yielding && v.speed < self.config.stopped_speed;
+                let in_center = v.obb().overlaps(&center_box);
+                let committed = !v.turned && v.distance_to_stop_line(center) < 0.0;
+                (in_center || committed) && !waiting
             })
             .count();
-        
-        let center_empty = center_count == 0;
 
-        let should_switch = match self.active_green {
-            None => true,
-            Some(_) => center_empty && self.green_timer >= MIN_GREEN_TIME,
+        let active_phase = match &self.interval {
+            SignalInterval::Green(phase) => Some(phase),
+            _ => None,
         };
 
-        if should_switch {
-            self.decide_next_green(vehicles);
+        let observation = Observation {
+            time,
+            vehicles,
+            phases: &self.phases,
+            detectors: &self.detectors,
+            queues: queue_lengths(vehicles),
+            oldest_wait: oldest_waits(vehicles),
+            active_phase,
+            green_elapsed: self.interval_timer,
+            center_empty: center_count == 0,
+            center,
+        };
+
+        // A controller asking for conflicting movements is ignored.
+        self.controller
+            .decide(&observation)
+            .filter(|p| self.conflicts.is_compatible(&p.movements))
+    }
+
+    pub fn active_phase(&self) -> Option<&Phase> {
+        match &self.interval {
+            SignalInterval::Green(phase) => Some(phase),
+            _ => None,
         }
     }
 
-    fn decide_next_green(&mut self, vehicles: &[Vehicle]) {
-        let n_c = vehicles.iter().filter(|v| v.origin == Origin::North && !v.turned).count();
-        let s_c = vehicles.iter().filter(|v| v.origin == Origin::South && !v.turned).count();
-        let e_c = vehicles.iter().filter(|v| v.origin == Origin::East && !v.turned).count();
-        let w_c = vehicles.iter().filter(|v| v.origin == Origin::West && !v.turned).count();
-
-        let ratios = HashMap::from([
-            (Origin::North, n_c as f32),
-            (Origin::South, s_c as f32),
-            (Origin::East, e_c as f32),
-            (Origin::West, w_c as f32),
-        ]);
-
-        let mut best_lane = None;
-        let mut best_score = -1.0;
-
-        for lane in [Origin::North, Origin::South, Origin::East, Origin::West] {
-            let score = ratios[&lane];
-            
-            if score > best_score && score > 0.0 {
-                best_score = score;
-                best_lane = Some(lane);
-            }
+    pub fn interval(&self) -> &SignalInterval {
+        &self.interval
+    }
+
+    pub fn aspect(&self, movement: Movement) -> SignalAspect {
+        if let Some(&(_, aspect)) = self.carried.iter().find(|(m, _)| *m == movement) {
+            return aspect;
         }
 
-        if let Some(lane) = best_lane {
-             if self.active_green != Some(lane) {
-                self.active_green = Some(lane);
-                self.green_timer = 0.0;
-             }
-        } else if self.active_green.is_none() {
-             self.active_green = Some(Origin::North);
+        match &self.interval {
+            SignalInterval::Green(phase) => phase.aspect(movement),
+            SignalInterval::Amber(phase) if phase.serves(movement) => SignalAspect::Amber,
+            _ => SignalAspect::Red,
         }
     }
-    
-    pub fn get_active_green(&self) -> Option<Origin> {
-        self.active_green
-    }
 
     pub fn draw(&self, center: (f32, f32)) {
-         let get_color = |o: Origin| {
-             if self.active_green == Some(o) { COLOR_LIGHT_GO } else { COLOR_LIGHT_STOP }
-         };
+         let size = self.config.light_size;
+         let padding = self.config.light_padding;
+         let radius = size / 2.0;
+         let width = size * 3.0 + padding * 4.0;
+         let height = size * 3.0 + padding * 4.0;
+         let edge = self.config.road_width / 2.0 + padding;
+
+         // Each head sits on the grass corner to the right of its approach and
+         // has one column per movement: left, straight, right.
+         let draw_head = |sx: f32, sy: f32, origin: Origin| {
+            let x = if sx < 0.0 { center.0 - edge - width } else { center.0 + edge };
+            let y = if sy < 0.0 { center.1 - edge - height } else { center.1 + edge };
+
+            draw_rectangle(x, y, width, height, COLOR_LIGHT_HOUSING);
+
+            let lamps = [COLOR_LIGHT_STOP, COLOR_LIGHT_AMBER, COLOR_LIGHT_GO];
+            let blink_on = get_time().fract() < 0.5;
 
-         let padding = LIGHT_PADDING;
-         let radius = LIGHT_SIZE / 2.0;
-         let full_size = LIGHT_SIZE + padding * 2.0;
-
-         let draw_light = |x: f32, y: f32, color: Color| {
-            draw_rectangle(x, y, full_size, full_size, COLOR_LIGHT_HOUSING);
-            
-            let cx = x + padding + radius;
-            let cy = y + padding + radius;
-            
-            draw_circle(cx, cy, radius, color);
+            for (col, route) in Route::ALL.into_iter().enumerate() {
+                // Permissive movements get a flashing amber arrow.
+                let lit = match self.aspect(Movement::new(origin, route)) {
+                    SignalAspect::Red => Some(0),
+                    SignalAspect::Amber => Some(1),
+                    SignalAspect::Permissive => blink_on.then_some(1),
+                    SignalAspect::Green => Some(2),
+                };
+                let cx = x + padding + radius + col as f32 * (size + padding);
+
+                for (i, color) in lamps.iter().enumerate() {
+                    let cy = y + padding + radius + i as f32 * (size + padding);
+                    if lit != Some(i) {
+                        draw_circle(cx, cy, radius, COLOR_LIGHT_OFF);
+                        continue;
+                    }
+
+                    draw_circle(cx, cy, radius, *color);
+                    draw_arrow((cx, cy), exit_direction(origin, route), radius * 0.7);
+                }
+            }
          };
 
-         draw_light(center.0 - LIGHT_OFFSET - padding, center.1 - LIGHT_OFFSET - padding, get_color(Origin::North));
-         draw_light(center.0 + LANE_OFFSET - padding, center.1 + LANE_OFFSET - padding, get_color(Origin::South));
-         draw_light(center.0 - LIGHT_OFFSET - padding, center.1 + LANE_OFFSET - padding, get_color(Origin::East));
-         draw_light(center.0 + LANE_OFFSET - padding, center.1 - LIGHT_OFFSET - padding, get_color(Origin::West));
+         draw_head(-1.0, -1.0, Origin::North);
+         draw_head(1.0, 1.0, Origin::South);
+         draw_head(-1.0, 1.0, Origin::East);
+         draw_head(1.0, -1.0, Origin::West);
     }
 }
+
+fn draw_arrow(at: (f32, f32), dir: (f32, f32), size: f32) {
+    let tip = (at.0 + dir.0 * size, at.1 + dir.1 * size);
+    let tail = (at.0 - dir.0 * size, at.1 - dir.1 * size);
+    let side = scale(right_normal(dir), size * 0.6);
+    let base = (at.0 + dir.0 * size * 0.1, at.1 + dir.1 * size * 0.1);
+    let dark = Color::new(0.0, 0.0, 0.0, 0.6);
+
+    draw_line(tail.0, tail.1, base.0, base.1, 2.0, dark);
+    draw_triangle(
+        vec2(tip.0, tip.1),
+        vec2(base.0 + side.0, base.1 + side.1),
+        vec2(base.0 - side.0, base.1 - side.1),
+        dark,
+    );
+}
@@ -1,182 +1,454 @@
 use macroquad::prelude::*;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::f32::consts::PI;
+use std::sync::Arc;
 
-use crate::app_config::*;
-use crate::types::{Origin, Route};
-
-static VEHICLE_ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
+use crate::app_config::SimConfig;
+use crate::control::phase::ConflictMatrix;
+use crate::entity::path::{entry_point, heading_of, travel_direction, TurnArc};
+use crate::geometry::{dot, sub, Obb};
+use crate::render::palette::*;
+use crate::types::{Movement, Origin, Route, SignalAspect, VehicleClass};
 
 #[derive(Debug, Clone)]
 pub struct Vehicle {
     pub id: usize,
+    pub class: VehicleClass,
     pub pos: (f32, f32),
-    pub speed: (f32, f32),
+    pub prev_pos: (f32, f32),
+    pub heading: f32,
+    pub prev_heading: f32,
+    pub speed: f32,
+    pub length: f32,
+    pub width: f32,
+    pub max_speed: f32,
+    pub max_accel: f32,
     pub origin: Origin,
     pub route: Route,
     pub color: Color,
     pub turned: bool,
+    pub turn_arc: Option<TurnArc>,
+    pub arc_progress: f32,
+    pub age: f32,
+    pub delay: f32,
+    pub wait_time: f32,
+    pub stops: usize,
+    // Time spent in the approach's entry queue before there was room to enter.
+    pub entry_wait: f32,
+    pub yielding: bool,
+    pub config: Arc<SimConfig>,
 }
 
 impl Vehicle {
-    pub fn new(pos: (f32, f32), speed: (f32, f32), origin: Origin, route: Route, color: Color) -> Self {
+    // Starts heading along its approach's direction of travel.
+    pub fn new(id: usize, class: VehicleClass, pos: (f32, f32), origin: Origin, route: Route, color: Color, config: Arc<SimConfig>) -> Self {
+        let spec = class.spec(&config);
+        let heading = heading_of(travel_direction(origin));
+
         Self {
-            id: VEHICLE_ID_COUNTER.fetch_add(1, Ordering::Relaxed),
+            id,
+            class,
             pos,
-            speed,
+            prev_pos: pos,
+            heading,
+            prev_heading: heading,
+            speed: spec.max_speed,
+            length: spec.length,
+            width: spec.width,
+            max_speed: spec.max_speed,
+            max_accel: spec.max_accel,
             origin,
             route,
             color,
             turned: false,
+            turn_arc: None,
+            arc_progress: 0.0,
+            age: 0.0,
+            delay: 0.0,
+            wait_time: 0.0,
+            stops: 0,
+            entry_wait: 0.0,
+            yielding: false,
+            config,
         }
     }
 
-    pub fn draw(&self) {
-        draw_rectangle(
-            self.pos.0 - CAR_SIZE / 2.0,
-            self.pos.1 - CAR_SIZE / 2.0,
-            CAR_SIZE,
-            CAR_SIZE,
-            self.color,
-        );
+    pub fn draw(&self, alpha: f32) {
+        let pos = self.interpolated_pos(alpha);
+        let heading = self.interpolated_heading(alpha);
 
-        draw_rectangle_lines(
-            self.pos.0 - CAR_SIZE / 2.0,
-            self.pos.1 - CAR_SIZE / 2.0,
-            CAR_SIZE,
-            CAR_SIZE,
-            2.0,
-            Color::new(0.0, 0.0, 0.0, 0.2),
-        );
-    }
+        // Draws a sub-rectangle given in body coordinates: `x` runs from the
+        // rear (-length / 2) to the front (+length / 2), `y` across the body.
+        let part = |x: f32, y: f32, w: f32, h: f32, color: Color| {
+            let (cos, sin) = (heading.cos(), heading.sin());
+            let cx = x + w / 2.0;
+            let cy = y + h / 2.0;
+            draw_rectangle_ex(
+                pos.0 + cx * cos - cy * sin,
+                pos.1 + cx * sin + cy * cos,
+                w,
+                h,
+                DrawRectangleParams {
+                    offset: vec2(0.5, 0.5),
+                    rotation: heading,
+                    color,
+                },
+            );
+        };
 
-    pub fn update(&mut self, center: (f32, f32)) {
-        self.pos.0 += self.speed.0;
-        self.pos.1 += self.speed.1;
-        
-        if !self.turned {
-            self.update_turning_direction(center);
-        }
-    }
-
-    pub fn should_despawn(&self) -> bool {
-        self.pos.0 < -DESPAWN_OFFSET
-            || self.pos.0 > screen_width() + DESPAWN_OFFSET
-            || self.pos.1 < -DESPAWN_OFFSET
-            || self.pos.1 > screen_height() + DESPAWN_OFFSET
-    }
-
-    fn update_turning_direction(&mut self, center: (f32, f32)) {
-        let s = CAR_SPEED;
-        match self.route {
-            Route::Straight => {
-                let passed = match self.origin {
-                    Origin::South => self.pos.1 < center.1 - LANE_OFFSET,
-                    Origin::North => self.pos.1 > center.1 + LANE_OFFSET,
-                    Origin::East => self.pos.0 > center.0 + LANE_OFFSET,
-                    Origin::West => self.pos.0 < center.0 - LANE_OFFSET,
-                };
-                if passed {
-                    self.turned = true;
-                }
+        let (l, w) = (self.length, self.width);
+        let half_l = l / 2.0;
+        let half_w = w / 2.0;
+        let shade = Color::new(0.0, 0.0, 0.0, 0.35);
+
+        match self.class {
+            VehicleClass::Car => {
+                part(-half_l, -half_w, l, w, self.color);
+                part(half_l * 0.3, -half_w * 0.8, l * 0.18, w * 0.8, shade);
+            }
+            VehicleClass::Truck => {
+                part(-half_l, -half_w, l * 0.72, w, COLOR_TRUCK_CARGO);
+                part(half_l * 0.48, -half_w, l * 0.26, w, self.color);
+                part(half_l * 0.72, -half_w * 0.8, l * 0.08, w * 0.8, shade);
             }
-            Route::Right => {
-                let turn_p = match self.origin {
-                    Origin::South => self.pos.1 < center.1 + CAR_SIZE,
-                    Origin::North => self.pos.1 > center.1 - CAR_SIZE,
-                    Origin::East => self.pos.0 > center.0 - CAR_SIZE,
-                    Origin::West => self.pos.0 < center.0 + CAR_SIZE,
-                };
-                if turn_p {
-                    self.speed = match self.origin {
-                        Origin::South => (s, 0.0),
-                        Origin::North => (-s, 0.0),
-                        Origin::East => (0.0, s),
-                        Origin::West => (0.0, -s),
-                    };
-                    self.turned = true;
-                }
+            VehicleClass::Bus => {
+                part(-half_l, -half_w, l, w, self.color);
+                part(-half_l * 0.8, -half_w * 0.7, l * 0.8, w * 0.18, shade);
+                part(-half_l * 0.8, half_w * 0.52, l * 0.8, w * 0.18, shade);
+                part(half_l * 0.8, -half_w * 0.8, l * 0.08, w * 0.8, shade);
             }
-            Route::Left => {
-                let turn_p = match self.origin {
-                    Origin::South => self.pos.1 < center.1 - CAR_SIZE,
-                    Origin::North => self.pos.1 > center.1 + CAR_SIZE,
-                    Origin::East => self.pos.0 > center.0 + CAR_SIZE,
-                    Origin::West => self.pos.0 < center.0 - CAR_SIZE,
-                };
-                if turn_p {
-                    self.speed = match self.origin {
-                        Origin::South => (-s, 0.0),
-                        Origin::North => (s, 0.0),
-                        Origin::East => (0.0, -s),
-                        Origin::West => (0.0, s),
-                    };
-                    self.turned = true;
-                }
+            VehicleClass::Motorcycle => {
+                part(-half_l, -half_w, l, w, self.color);
+                draw_circle(pos.0, pos.1, w * 0.6, COLOR_RIDER);
             }
         }
+
+        let corners = Obb::new(pos, heading, l, w).corners();
+        for i in 0..4 {
+            let (a, b) = (corners[i], corners[(i + 1) % 4]);
+            draw_line(a.0, a.1, b.0, b.1, 2.0, Color::new(0.0, 0.0, 0.0, 0.2));
+        }
+    }
+
+    pub fn interpolated_pos(&self, alpha: f32) -> (f32, f32) {
+        (
+            self.prev_pos.0 + (self.pos.0 - self.prev_pos.0) * alpha,
+            self.prev_pos.1 + (self.pos.1 - self.prev_pos.1) * alpha,
+        )
+    }
+
+    pub fn interpolated_heading(&self, alpha: f32) -> f32 {
+        let delta = (self.heading - self.prev_heading + PI).rem_euclid(2.0 * PI) - PI;
+        self.prev_heading + delta * alpha
     }
 
-    pub fn is_safe_to_move(&self, vehicles: &[Vehicle], active_green: Option<Origin>, center: (f32, f32)) -> bool {
-        let mut can_move = true;
+    pub fn obb(&self) -> Obb {
+        Obb::new(self.pos, self.heading, self.length, self.width)
+    }
+
+    pub fn movement(&self) -> Movement {
+        Movement::new(self.origin, self.route)
+    }
+
+    pub fn direction(&self) -> (f32, f32) {
+        (self.heading.cos(), self.heading.sin())
+    }
+
+    pub fn update(&mut self, dt: f32, acceleration: f32, center: (f32, f32)) {
+        let new_speed = (self.speed + acceleration * dt).max(0.0);
+        self.age += dt;
+        self.delay += dt * (1.0 - new_speed / self.max_speed).max(0.0);
+        if !self.turned && new_speed < self.config.stopped_speed {
+            self.wait_time += dt;
+        }
+        if self.speed >= self.config.stopped_speed && new_speed < self.config.stopped_speed {
+            self.stops += 1;
+        }
+        let distance = (self.speed + new_speed) / 2.0 * dt;
+        self.speed = new_speed;
+
+        if self.turn_arc.is_some() {
+            self.advance_on_arc(distance);
+            return;
+        }
+
+        let dir = self.direction();
+        self.pos.0 += dir.0 * distance;
+        self.pos.1 += dir.1 * distance;
 
         if !self.turned {
-            let is_at_stop = match self.origin {
-                Origin::South => {
-                    self.pos.1 > center.1 + LANE_OFFSET && self.pos.1 < center.1 + (LANE_OFFSET + CAR_SIZE * 1.5)
-                }
-                Origin::North => {
-                    self.pos.1 < center.1 - LANE_OFFSET && self.pos.1 > center.1 - (LANE_OFFSET + CAR_SIZE * 1.5)
-                }
-                Origin::East => {
-                    self.pos.0 < center.0 - LANE_OFFSET && self.pos.0 > center.0 - (LANE_OFFSET + CAR_SIZE * 1.5)
-                }
-                Origin::West => {
-                    self.pos.0 > center.0 + LANE_OFFSET && self.pos.0 < center.0 + (LANE_OFFSET + CAR_SIZE * 1.5)
-                }
-            };
+            self.update_maneuver(center);
+        }
+    }
+
+    pub fn should_despawn(&self, bounds: (f32, f32)) -> bool {
+        let offset = self.config.despawn_offset;
+        self.pos.0 < -offset || self.pos.0 > bounds.0 + offset || self.pos.1 < -offset || self.pos.1 > bounds.1 + offset
+    }
 
-            if is_at_stop && Some(self.origin) != active_green {
-                can_move = false;
+    fn update_maneuver(&mut self, center: (f32, f32)) {
+        let Some(arc) = TurnArc::for_movement(self.origin, self.route, center, &self.config) else {
+            let passed = match self.origin {
+                Origin::South => self.pos.1 < center.1 - self.config.lane_offset,
+                Origin::North => self.pos.1 > center.1 + self.config.lane_offset,
+                Origin::East => self.pos.0 > center.0 + self.config.lane_offset,
+                Origin::West => self.pos.0 < center.0 - self.config.lane_offset,
+            };
+            if passed {
+                self.turned = true;
             }
+            return;
+        };
+
+        let start = arc.start();
+        let overshoot = (self.pos.0 - start.0) * arc.entry_dir.0 + (self.pos.1 - start.1) * arc.entry_dir.1;
+        if overshoot >= 0.0 {
+            self.turn_arc = Some(arc);
+            self.arc_progress = 0.0;
+            self.advance_on_arc(overshoot);
+        }
+    }
+
+    fn advance_on_arc(&mut self, distance: f32) {
+        let Some(arc) = self.turn_arc else {
+            return;
+        };
+
+        self.arc_progress += distance;
+
+        if self.arc_progress >= arc.length() {
+            let leftover = self.arc_progress - arc.length();
+            let end = arc.end();
+            self.pos = (end.0 + arc.exit_dir.0 * leftover, end.1 + arc.exit_dir.1 * leftover);
+            self.heading = heading_of(arc.exit_dir);
+            self.turn_arc = None;
+            self.turned = true;
+        } else {
+            self.pos = arc.point_at(self.arc_progress);
+            self.heading = arc.heading_at(self.arc_progress);
+        }
+    }
+
+    // `yield_gap` is the result of `Vehicle::yield_gap` for this tick.
+    pub fn acceleration(&self, vehicles: &[Vehicle], aspect: SignalAspect, yield_gap: Option<f32>, center: (f32, f32)) -> f32 {
+        let mut acceleration = self.idm_acceleration(None);
+
+        if !self.turned && self.must_stop(aspect, center) {
+            let gap = self.distance_to_stop_line(center);
+            acceleration = acceleration.min(self.idm_acceleration(Some((gap, self.speed))));
+        }
+
+        if let Some(gap) = yield_gap {
+            acceleration = acceleration.min(self.idm_acceleration(Some((gap, self.speed))));
+        }
+
+        if let Some((gap, leader_speed)) = self.find_leader(vehicles) {
+            acceleration = acceleration.min(self.idm_acceleration(Some((gap, self.speed - leader_speed))));
+        }
+
+        acceleration
+    }
+
+    // At amber a driver stops only if the stop line is still beyond their
+    // comfortable braking distance; otherwise they carry on through.
+    fn must_stop(&self, aspect: SignalAspect, center: (f32, f32)) -> bool {
+        let gap = self.distance_to_stop_line(center);
+        if gap < 0.0 {
+            return false;
         }
 
-        if can_move {
-            for other in vehicles {
-                if self.id == other.id {
-                    continue;
-                }
-                
-                let dx = self.pos.0 - other.pos.0;
-                let dy = self.pos.1 - other.pos.1;
-                let dist_sq = dx * dx + dy * dy;
-
-                if dist_sq < AHEAD_CHECK_DISTANCE * AHEAD_CHECK_DISTANCE &&
-                   self.is_ahead(other.pos)
-                {
-                    can_move = false;
-                    break;
-                }
+        match aspect {
+            SignalAspect::Green | SignalAspect::Permissive => false,
+            SignalAspect::Amber => gap >= self.speed * self.speed / (2.0 * self.config.comfort_decel),
+            SignalAspect::Red => true,
+        }
+    }
+
+    // Intelligent Driver Model; `leader` is the bumper gap and approach rate to the obstacle ahead.
+    fn idm_acceleration(&self, leader: Option<(f32, f32)>) -> f32 {
+        let config = &self.config;
+        let free_road = 1.0 - (self.speed / self.max_speed).powf(config.idm_delta);
+
+        let interaction = match leader {
+            Some((gap, approach_rate)) => {
+                let desired_gap = config.min_gap
+                    + (self.speed * config.time_headway
+                        + self.speed * approach_rate / (2.0 * (self.max_accel * config.comfort_decel).sqrt()))
+                    .max(0.0);
+                (desired_gap / gap.max(0.1)).powi(2)
             }
+            None => 0.0,
+        };
+
+        self.max_accel * (free_road - interaction)
+    }
+
+    pub fn distance_to_stop_line(&self, center: (f32, f32)) -> f32 {
+        let half = self.length / 2.0;
+        let edge = self.config.road_width / 2.0;
+
+        match self.origin {
+            Origin::South => (self.pos.1 - half) - (center.1 + edge),
+            Origin::North => (center.1 - edge) - (self.pos.1 + half),
+            Origin::East => (center.0 - edge) - (self.pos.0 + half),
+            Origin::West => (self.pos.0 - half) - (center.0 + edge),
+        }
+    }
+
+    // Distance of the vehicle's center along its movement's path, measured
+    // from the stop line; vehicles that finished a turn are past every zone.
+    pub fn path_progress(&self, center: (f32, f32)) -> f32 {
+        if self.turn_arc.is_some() {
+            return self.arc_progress;
+        }
+        if self.turned && self.route != Route::Straight {
+            return f32::MAX;
+        }
+        dot(sub(self.pos, entry_point(self.origin, center, &self.config)), travel_direction(self.origin))
+    }
+
+    // Gap acceptance for a permissive movement. The vehicle waits short of
+    // the first zone it shares with any other movement and only goes once
+    // every conflicting vehicle leaves a big enough gap; after that it is
+    // committed and never stops to yield inside the box. Returns the bumper
+    // gap to the yield point while the vehicle has to wait.
+    pub fn yield_gap(
+        &self,
+        vehicles: &[Vehicle],
+        conflicts: &ConflictMatrix,
+        aspect_of: impl Fn(Movement) -> SignalAspect,
+        center: (f32, f32),
+    ) -> Option<f32> {
+        if !self.yielding || self.turned {
+            return None;
+        }
+
+        let yield_point = Movement::all()
+            .filter_map(|m| conflicts.zone(self.movement(), m))
+            .map(|zone| zone.enter - self.config.yield_margin)
+            .fold(f32::MAX, f32::min);
+
+        let front = self.path_progress(center) + self.length / 2.0;
+        if front > yield_point {
+            return None;
         }
 
-        can_move
+        vehicles
+            .iter()
+            .any(|other| other.id != self.id && self.rejects_gap(other, vehicles, conflicts, &aspect_of, center))
+            .then(|| (yield_point - front).max(0.0))
     }
 
-    fn is_ahead(&self, other: (f32, f32)) -> bool {
-        let epsilon = 0.1;
-        
-        if self.speed.0 > epsilon { 
-            return other.0 > self.pos.0 && (other.1 - self.pos.1).abs() < AHEAD_CHECK_LATERAL; 
+    fn rejects_gap(
+        &self,
+        other: &Vehicle,
+        vehicles: &[Vehicle],
+        conflicts: &ConflictMatrix,
+        aspect_of: &impl Fn(Movement) -> SignalAspect,
+        center: (f32, f32),
+    ) -> bool {
+        let Some(zone) = conflicts.zone(other.movement(), self.movement()) else {
+            return false;
+        };
+
+        let progress = other.path_progress(center);
+        if progress - other.length / 2.0 > zone.exit {
+            return false;
         }
-        if self.speed.0 < -epsilon { 
-            return other.0 < self.pos.0 && (other.1 - self.pos.1).abs() < AHEAD_CHECK_LATERAL; 
+
+        let front = progress + other.length / 2.0;
+        if front >= zone.enter {
+            return true;
         }
-        if self.speed.1 > epsilon { 
-            return other.1 > self.pos.1 && (other.0 - self.pos.0).abs() < AHEAD_CHECK_LATERAL; 
+
+        if other.distance_to_stop_line(center) > 0.0 && aspect_of(other.movement()) == SignalAspect::Red {
+            return false;
         }
-        if self.speed.1 < -epsilon { 
-            return other.1 < self.pos.1 && (other.0 - self.pos.0).abs() < AHEAD_CHECK_LATERAL; 
+
+        // A vehicle queued behind a stopped one cannot arrive before it does;
+        // the one at the head of the queue is judged on its own.
+        let arriving = other.time_to_cover(zone.enter - front) < self.config.critical_gap && !other.is_queued(vehicles);
+
+        // Two permissive vehicles waiting on each other would deadlock, so
+        // the one that spawned first has priority.
+        if other.yielding {
+            return other.id < self.id && (arriving || other.distance_to_stop_line(center) < 0.0);
         }
-        false
+
+        arriving
+    }
+
+    fn is_queued(&self, vehicles: &[Vehicle]) -> bool {
+        self.speed < self.config.stopped_speed
+            && self
+                .find_leader(vehicles)
+                .is_some_and(|(gap, speed)| gap < self.config.min_gap * 2.0 && speed < self.config.stopped_speed)
+    }
+
+    // Time to cover `distance` from the current speed at full acceleration;
+    // a vehicle waiting at a green can be at the conflict zone quickly.
+    fn time_to_cover(&self, distance: f32) -> f32 {
+        let v = self.speed;
+        ((v * v + 2.0 * self.max_accel * distance).sqrt() - v) / self.max_accel
+    }
+
+    // Sweeps the vehicle's footprint forward and returns the bumper gap and
+    // along-track speed of the nearest body that intersects the sweep.
+    fn find_leader(&self, vehicles: &[Vehicle]) -> Option<(f32, f32)> {
+        let dir = self.direction();
+        let front = self.length / 2.0;
+        let sweep = self.sweep();
+
+        let mut leader: Option<(f32, f32)> = None;
+
+        for other in vehicles {
+            if self.id == other.id {
+                continue;
+            }
+
+            let body = other.obb();
+            if !body.overlaps(&sweep) {
+                continue;
+            }
+
+            // Two vehicles that met head-on in the box would each wait for
+            // the other forever; the one that spawned first goes on.
+            if self.id < other.id && dot(other.direction(), dir) < 0.0 && other.sweep().overlaps(&self.obb()) {
+                continue;
+            }
+
+            let gap = body
+                .corners()
+                .iter()
+                .map(|&c| dot(sub(c, self.pos), dir))
+                .fold(f32::MAX, f32::min)
+                - front;
+
+            if leader.is_none_or(|(best, _)| gap < best) {
+                let along = dot(other.direction(), dir);
+                leader = Some((gap.max(0.0), other.speed * along.max(0.0)));
+            }
+        }
+
+        leader
+    }
+
+    // The footprint swept `ahead_check_distance` forward along the heading.
+    fn sweep(&self) -> Obb {
+        let dir = self.direction();
+        let front = self.length / 2.0;
+        let reach = self.config.ahead_check_distance;
+        Obb {
+            center: (
+                self.pos.0 + dir.0 * (front + reach / 2.0),
+                self.pos.1 + dir.1 * (front + reach / 2.0),
+            ),
+            axis: dir,
+            half_length: reach / 2.0,
+            half_width: self.width / 2.0 + self.config.ahead_check_lateral,
+        }
+    }
+
+    pub fn overlaps(&self, other: &Vehicle) -> bool {
+        self.obb().overlaps(&other.obb())
     }
 }
@@ -0,0 +1,58 @@
+use crate::app_config::*;
+use crate::types::VehicleClass;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ClassSpec {
+    pub length: f32,
+    pub width: f32,
+    pub max_speed: f32,
+    pub max_accel: f32,
+    pub spawn_weight: f32,
+}
+
+impl VehicleClass {
+    // Cars use the vehicle settings of the configuration, the other classes
+    // its `classes` section.
+    pub fn spec(self, config: &SimConfig) -> ClassSpec {
+        let classes = &config.classes;
+        match self {
+            VehicleClass::Car => ClassSpec {
+                length: config.vehicle_length,
+                width: config.vehicle_width,
+                max_speed: config.car_speed,
+                max_accel: config.max_accel,
+                spawn_weight: classes.car_spawn_weight,
+            },
+            VehicleClass::Truck => ClassSpec {
+                length: classes.truck_length,
+                width: classes.truck_width,
+                max_speed: classes.truck_max_speed,
+                max_accel: classes.truck_max_accel,
+                spawn_weight: classes.truck_spawn_weight,
+            },
+            VehicleClass::Bus => ClassSpec {
+                length: classes.bus_length,
+                width: classes.bus_width,
+                max_speed: classes.bus_max_speed,
+                max_accel: classes.bus_max_accel,
+                spawn_weight: classes.bus_spawn_weight,
+            },
+            VehicleClass::Motorcycle => ClassSpec {
+                length: classes.motorcycle_length,
+                width: classes.motorcycle_width,
+                max_speed: classes.motorcycle_max_speed,
+                max_accel: classes.motorcycle_max_accel,
+                spawn_weight: classes.motorcycle_spawn_weight,
+            },
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            VehicleClass::Car => "Car",
+            VehicleClass::Truck => "Truck",
+            VehicleClass::Bus => "Bus",
+            VehicleClass::Motorcycle => "Motorcycle",
+        }
+    }
+}
@@ -0,0 +1,82 @@
+pub fn add(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    (a.0 + b.0, a.1 + b.1)
+}
+
+pub fn sub(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+pub fn scale(a: (f32, f32), k: f32) -> (f32, f32) {
+    (a.0 * k, a.1 * k)
+}
+
+pub fn dot(a: (f32, f32), b: (f32, f32)) -> f32 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+// Screen space has y pointing down, so the right-hand side of `dir` is (-y, x).
+pub fn right_normal(dir: (f32, f32)) -> (f32, f32) {
+    (-dir.1, dir.0)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Obb {
+    pub center: (f32, f32),
+    pub axis: (f32, f32),
+    pub half_length: f32,
+    pub half_width: f32,
+}
+
+impl Obb {
+    pub fn new(center: (f32, f32), heading: f32, length: f32, width: f32) -> Self {
+        Self {
+            center,
+            axis: (heading.cos(), heading.sin()),
+            half_length: length / 2.0,
+            half_width: width / 2.0,
+        }
+    }
+
+    pub fn inflated(&self, margin: f32) -> Self {
+        Self {
+            half_length: self.half_length + margin,
+            half_width: self.half_width + margin,
+            ..*self
+        }
+    }
+
+    pub fn corners(&self) -> [(f32, f32); 4] {
+        let along = scale(self.axis, self.half_length);
+        let across = scale(right_normal(self.axis), self.half_width);
+
+        [
+            add(add(self.center, along), across),
+            sub(add(self.center, along), across),
+            sub(sub(self.center, along), across),
+            add(sub(self.center, along), across),
+        ]
+    }
+
+    // Separating axis test; two rectangles only need their own four edge normals.
+    pub fn overlaps(&self, other: &Obb) -> bool {
+        let axes = [
+            self.axis,
+            right_normal(self.axis),
+            other.axis,
+            right_normal(other.axis),
+        ];
+
+        axes.iter().all(|&axis| {
+            let (a_min, a_max) = self.project(axis);
+            let (b_min, b_max) = other.project(axis);
+            a_min < b_max && b_min < a_max
+        })
+    }
+
+    pub fn project(&self, axis: (f32, f32)) -> (f32, f32) {
+        self.corners()
+            .iter()
+            .map(|&c| dot(c, axis))
+            .fold((f32::MAX, f32::MIN), |(lo, hi), p| (lo.min(p), hi.max(p)))
+    }
+}
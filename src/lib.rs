@@ -0,0 +1,57 @@
+pub mod app_config;
+pub mod types;
+pub mod geometry;
+pub mod entity {
+    pub mod vehicle;
+    pub mod traffic_light;
+    pub mod detector;
+    pub mod path;
+    pub mod vehicle_class;
+}
+pub mod manager {
+    pub mod intersection;
+    pub mod headless;
+    pub mod demand;
+    pub mod turning;
+    pub mod scenario;
+    pub mod environment;
+    pub mod input;
+    pub mod spawner;
+    pub mod simulation;
+    pub mod statistics;
+}
+pub mod control {
+    pub mod controller;
+    pub mod actuated;
+    pub mod demand_based;
+    pub mod fixed_time;
+    pub mod max_pressure;
+    pub mod phase;
+    pub mod plan;
+    pub mod webster;
+}
+pub mod optimize {
+    pub mod genetic;
+    pub mod timing;
+}
+pub mod render {
+    pub mod draw;
+    pub mod palette;
+}
+
+pub use app_config::{ConfigError, SimConfig};
+pub use control::controller::{Observation, SignalController};
+pub use control::phase::{ConflictMatrix, DualRing, Phase};
+pub use control::plan::SignalPlan;
+pub use entity::detector::{Detector, DetectorKind};
+pub use entity::traffic_light::{SignalInterval, TrafficLightSystem};
+pub use entity::vehicle::Vehicle;
+pub use manager::demand::{DemandProfile, Headway};
+pub use manager::environment::{EnvConfig, EnvError, EnvObservation, RewardWeights, SignalEnv};
+pub use manager::headless::HeadlessRun;
+pub use manager::scenario::{ControllerSpec, EventAction, Scenario, ScenarioEvent};
+pub use manager::simulation::Simulation;
+pub use manager::spawner::Spawner;
+pub use manager::statistics::Statistics;
+pub use manager::turning::{TurningMatrix, TurningRatios};
+pub use types::{Movement, Origin, Route, SignalAspect, VehicleClass};
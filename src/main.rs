@@ -1,21 +1,6 @@
-mod app_config;
-mod types;
-mod entity {
-    pub mod vehicle;
-    pub mod traffic_light;
-}
-mod manager {
-    pub mod intersection;
-    pub mod input;
-    pub mod spawner;
-}
-mod render {
-    pub mod draw;
-    pub mod palette;
-}
-
 use macroquad::prelude::*;
-use manager::intersection::IntersectionManager;
+use road_intersection::manager::intersection::IntersectionManager;
+use road_intersection::{ConfigError, DemandProfile, Headway, Scenario, SignalPlan, SimConfig, TurningMatrix};
 
 fn window_conf() -> Conf {
     Conf {
@@ -27,9 +12,137 @@ fn window_conf() -> Conf {
     }
 }
 
+// Without --scenario this is an empty scenario, so --config and --set still
+// reach the default controller's parameters.
+fn parse_scenario() -> Scenario {
+    let args: Vec<String> = std::env::args().collect();
+    let configure = |config| Ok(parse_config(config));
+
+    let scenario = match args.iter().position(|a| a == "--scenario").and_then(|i| args.get(i + 1)) {
+        Some(path) => Scenario::load_with(path, configure).map_err(|e| format!("{}: {}", path, e)),
+        None => Scenario::from_toml_with("", configure).map_err(|e| e.to_string()),
+    };
+    scenario.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+// `--config <file>` replaces the scenario's settings, then each
+// `--set name=value` adjusts one of them.
+fn parse_config(base: SimConfig) -> SimConfig {
+    let args: Vec<String> = std::env::args().collect();
+    let fail = |e: ConfigError| -> ! {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    };
+
+    let mut config = match args.iter().position(|a| a == "--config").and_then(|i| args.get(i + 1)) {
+        Some(path) => SimConfig::load(path).unwrap_or_else(|e| {
+            eprintln!("{}: {}", path, e);
+            std::process::exit(1);
+        }),
+        None => base,
+    };
+    for setting in args.windows(2).filter(|w| w[0] == "--set").map(|w| &w[1]) {
+        config.set(setting).unwrap_or_else(|e| fail(e));
+    }
+    config.validate().unwrap_or_else(|e| fail(e));
+    config
+}
+
+fn parse_seed() -> Option<u64> {
+    let args: Vec<String> = std::env::args().collect();
+
+    args.iter()
+        .position(|a| a == "--seed")
+        .and_then(|i| args.get(i + 1))
+        .map(|s| {
+            s.parse().unwrap_or_else(|_| {
+                eprintln!("--seed expects a number, got '{}'", s);
+                std::process::exit(1);
+            })
+        })
+}
+
+fn parse_plans(config: &SimConfig) -> Vec<SignalPlan> {
+    let args: Vec<String> = std::env::args().collect();
+
+    args.windows(2)
+        .filter(|w| w[0] == "--plan")
+        .map(|w| {
+            SignalPlan::load_with(&w[1], config).unwrap_or_else(|e| {
+                eprintln!("{}: {}", w[1], e);
+                std::process::exit(1);
+            })
+        })
+        .collect()
+}
+
+fn parse_demand() -> Option<DemandProfile> {
+    let args: Vec<String> = std::env::args().collect();
+    let value = |name: &str| args.iter().position(|a| a == name).and_then(|i| args.get(i + 1));
+
+    if let Some(path) = value("--demand") {
+        return Some(DemandProfile::load(path).unwrap_or_else(|e| {
+            eprintln!("{}: {}", path, e);
+            std::process::exit(1);
+        }));
+    }
+
+    let rate: f32 = value("--rate").map(|s| {
+        s.parse().unwrap_or_else(|_| {
+            eprintln!("--rate expects a number, got '{}'", s);
+            std::process::exit(1);
+        })
+    })?;
+    let headway: Headway = value("--headway")
+        .map(|s| {
+            s.parse().unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            })
+        })
+        .unwrap_or_default();
+    let profile = DemandProfile::constant(rate, headway);
+    if let Err(e) = profile.validate() {
+        eprintln!("--rate: {}", e);
+        std::process::exit(1);
+    }
+    Some(profile)
+}
+
+fn parse_turning() -> Option<TurningMatrix> {
+    let args: Vec<String> = std::env::args().collect();
+
+    args.iter()
+        .position(|a| a == "--turning")
+        .and_then(|i| args.get(i + 1))
+        .map(|path| {
+            TurningMatrix::load(path).unwrap_or_else(|e| {
+                eprintln!("{}: {}", path, e);
+                std::process::exit(1);
+            })
+        })
+}
+
 #[macroquad::main(window_conf)]
 async fn main() {
-    let mut intersection = IntersectionManager::new();
+    // Command-line options override the scenario.
+    let mut scenario = parse_scenario();
+    let seed = parse_seed().or(scenario.seed).unwrap_or_else(::rand::random);
+    if let Some(demand) = parse_demand() {
+        scenario.demand = Some(demand);
+    }
+    if let Some(turning) = parse_turning() {
+        scenario.turning = turning;
+    }
+
+    let plans = parse_plans(&scenario.config);
+    let mut intersection = IntersectionManager::new(seed, plans, scenario).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
 
     loop {
         intersection.update();
@@ -0,0 +1,245 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::types::Origin;
+
+// Ten arrivals a second on one approach, far beyond what a lane discharges.
+// Much higher rates give headways that vanish next to the clock's rounding.
+pub const MAX_RATE: f32 = 36_000.0;
+
+// How the gaps between consecutive arrivals on one approach are drawn, given
+// the mean headway `3600 / rate`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Headway {
+    // Poisson arrivals.
+    #[default]
+    Exponential,
+    // Exponential on top of a minimum headway, so vehicles never arrive closer
+    // than `min` seconds.
+    ShiftedExponential { min: f32 },
+    // Sum of `k` exponential phases: more regular than Poisson as `k` grows.
+    Erlang { k: u32 },
+    // Uniform within `spread` times the mean on either side of it.
+    Uniform { spread: f32 },
+}
+
+impl Headway {
+    pub fn sample<R: Rng>(&self, mean: f32, rng: &mut R) -> f32 {
+        match *self {
+            Headway::Exponential => exponential(mean, rng),
+            Headway::ShiftedExponential { min } => min + exponential((mean - min).max(0.0), rng),
+            Headway::Erlang { k } => {
+                let k = k.max(1);
+                (0..k).map(|_| exponential(mean / k as f32, rng)).sum()
+            }
+            Headway::Uniform { spread } => mean * (1.0 + spread * rng.gen_range(-1.0..=1.0)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match *self {
+            Headway::ShiftedExponential { min } if !min.is_finite() || min < 0.0 => {
+                Err("a shifted-exponential minimum headway must be a finite, non-negative number".into())
+            }
+            Headway::Erlang { k: 0 } => Err("an Erlang headway needs k >= 1".into()),
+            Headway::Uniform { spread } if !(0.0..=1.0).contains(&spread) => Err("a uniform headway spread must be between 0 and 1".into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Command-line form: `exponential`, `shifted:<min>`, `erlang:<k>` or `uniform:<spread>`.
+impl FromStr for Headway {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = s.split_once(':').unwrap_or((s, ""));
+        let number = |what: &str| -> Result<f32, String> {
+            value.parse().map_err(|_| format!("'{}' needs a {}, e.g. {}:1.5", kind, what, kind))
+        };
+
+        let headway = match kind {
+            "exponential" => Headway::Exponential,
+            "shifted" => Headway::ShiftedExponential { min: number("minimum headway")? },
+            "erlang" => Headway::Erlang {
+                k: value.parse().map_err(|_| "'erlang' needs a shape, e.g. erlang:2".to_string())?,
+            },
+            "uniform" => Headway::Uniform { spread: number("spread")? },
+            _ => return Err(format!("unknown headway '{}'", s)),
+        };
+        headway.validate()?;
+        Ok(headway)
+    }
+}
+
+fn exponential<R: Rng>(mean: f32, rng: &mut R) -> f32 {
+    let u: f32 = rng.gen_range(f32::EPSILON..1.0);
+    -mean * u.ln()
+}
+
+// Arrival rates in vehicles per hour from `start` seconds until the next
+// period begins. Approaches that are left out get no traffic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DemandPeriod {
+    pub start: f32,
+    pub rates: HashMap<Origin, f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DemandProfile {
+    #[serde(default)]
+    pub headway: Headway,
+    pub periods: Vec<DemandPeriod>,
+}
+
+#[derive(Debug)]
+pub enum DemandError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for DemandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DemandError::Io(e) => write!(f, "cannot read demand profile: {}", e),
+            DemandError::Parse(e) => write!(f, "cannot parse demand profile: {}", e),
+            DemandError::Invalid(msg) => write!(f, "invalid demand profile: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for DemandError {}
+
+impl DemandProfile {
+    // The same rate on every approach, all the time.
+    pub fn constant(rate: f32, headway: Headway) -> Self {
+        Self {
+            headway,
+            periods: vec![DemandPeriod {
+                start: 0.0,
+                rates: Origin::ALL.into_iter().map(|o| (o, rate)).collect(),
+            }],
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DemandError> {
+        let text = fs::read_to_string(path).map_err(DemandError::Io)?;
+        Self::from_toml(&text)
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, DemandError> {
+        let profile: DemandProfile = toml::from_str(text).map_err(DemandError::Parse)?;
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    pub fn validate(&self) -> Result<(), DemandError> {
+        if self.periods.is_empty() {
+            return Err(DemandError::Invalid("a demand profile needs at least one period".into()));
+        }
+
+        self.headway.validate().map_err(DemandError::Invalid)?;
+
+        for (i, period) in self.periods.iter().enumerate() {
+            if i > 0 && period.start <= self.periods[i - 1].start {
+                return Err(DemandError::Invalid(format!("period {} does not start after period {}", i + 1, i)));
+            }
+            let invalid = Origin::ALL
+                .into_iter()
+                .filter_map(|o| period.rates.get(&o).map(|&r| (o, r)))
+                .find(|(_, r)| !(r.is_finite() && (0.0..=MAX_RATE).contains(r)));
+            if let Some((origin, rate)) = invalid {
+                return Err(DemandError::Invalid(format!(
+                    "period {} has an invalid rate {} for {:?}, expected 0 to {} veh/h",
+                    i + 1,
+                    rate,
+                    origin,
+                    MAX_RATE
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn rate(&self, origin: Origin, time: f32) -> f32 {
+        self.periods
+            .iter()
+            .rev()
+            .find(|p| p.start <= time)
+            .and_then(|p| p.rates.get(&origin).copied())
+            .unwrap_or(0.0)
+    }
+
+    // Total rate over all approaches, for display.
+    pub fn total_rate(&self, time: f32) -> f32 {
+        Origin::ALL.into_iter().map(|o| self.rate(o, time)).sum()
+    }
+
+    fn next_change(&self, time: f32) -> Option<f32> {
+        self.periods.iter().map(|p| p.start).find(|&start| start > time)
+    }
+}
+
+// Turns a profile into arrival times, one independent stream per approach.
+#[derive(Debug, Clone)]
+pub struct DemandGenerator {
+    profile: DemandProfile,
+    next_arrival: HashMap<Origin, f32>,
+}
+
+impl DemandGenerator {
+    pub fn new(profile: DemandProfile) -> Self {
+        Self {
+            profile,
+            next_arrival: HashMap::new(),
+        }
+    }
+
+    pub fn profile(&self) -> &DemandProfile {
+        &self.profile
+    }
+
+    // Approaches with an arrival due by `time`, in arrival order per approach.
+    pub fn arrivals<R: Rng>(&mut self, time: f32, rng: &mut R) -> Vec<Origin> {
+        let mut due = Vec::new();
+
+        for origin in Origin::ALL {
+            let mut next = match self.next_arrival.get(&origin) {
+                Some(&next) => next,
+                None => self.schedule(origin, time, rng),
+            };
+            while next <= time {
+                due.push(origin);
+                // A headway lost to rounding must still move the stream on.
+                next = self.schedule(origin, next, rng).max(next.next_up());
+            }
+            self.next_arrival.insert(origin, next);
+        }
+
+        due
+    }
+
+    // The headway is drawn at the rate in force at `from`; a period with no
+    // traffic pushes the draw to the start of the next period.
+    fn schedule<R: Rng>(&self, origin: Origin, from: f32, rng: &mut R) -> f32 {
+        let mut time = from;
+        loop {
+            let rate = self.profile.rate(origin, time);
+            if rate > 0.0 {
+                return time + self.profile.headway.sample(3600.0 / rate, rng);
+            }
+            match self.profile.next_change(time) {
+                Some(change) => time = change,
+                None => return f32::INFINITY,
+            }
+        }
+    }
+}
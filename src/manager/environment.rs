@@ -0,0 +1,260 @@
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::app_config::*;
+use crate::control::controller::{Observation, SignalController};
+use crate::control::phase::Phase;
+use crate::manager::demand::DemandProfile;
+use crate::manager::simulation::Simulation;
+use crate::manager::turning::TurningMatrix;
+use crate::types::Origin;
+
+// The reward of a step is
+// `-delay * vehicle-seconds of delay + throughput * vehicles out - stops * new stops`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RewardWeights {
+    pub delay: f32,
+    pub throughput: f32,
+    pub stops: f32,
+}
+
+impl Default for RewardWeights {
+    fn default() -> Self {
+        Self::delay()
+    }
+}
+
+impl RewardWeights {
+    pub fn delay() -> Self {
+        Self { delay: 1.0, throughput: 0.0, stops: 0.0 }
+    }
+
+    pub fn throughput() -> Self {
+        Self { delay: 0.0, throughput: 1.0, stops: 0.0 }
+    }
+
+    pub fn stops() -> Self {
+        Self { delay: 0.0, throughput: 0.0, stops: 1.0 }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvConfig {
+    // Simulated seconds between two actions.
+    pub decision_interval: f32,
+    pub episode_length: f32,
+    pub spawn_interval: f32,
+    // Replaces the fixed spawn interval when set.
+    pub demand: Option<DemandProfile>,
+    pub turning: TurningMatrix,
+    pub reward: RewardWeights,
+    pub simulation: SimConfig,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self::from_config(SimConfig::default())
+    }
+}
+
+impl EnvConfig {
+    // Decision interval and episode follow the configuration's headless section.
+    pub fn from_config(simulation: SimConfig) -> Self {
+        Self {
+            decision_interval: simulation.headless.decision_interval,
+            episode_length: simulation.headless.duration,
+            spawn_interval: simulation.headless.spawn_interval,
+            demand: None,
+            turning: TurningMatrix::uniform(),
+            reward: RewardWeights::default(),
+            simulation,
+        }
+    }
+}
+
+// Per-approach values are in `Origin::ALL` order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvObservation {
+    pub time: f32,
+    pub queues: [usize; 4],
+    // Vehicles that arrived but have not been able to enter yet.
+    pub entry_queues: [usize; 4],
+    pub waits: [f32; 4],
+    // Index of the green phase; `None` during amber and all-red.
+    pub phase: Option<usize>,
+    pub phase_count: usize,
+}
+
+impl EnvObservation {
+    // Queues, entry queues, waits and a one-hot phase as one flat feature vector.
+    pub fn features(&self) -> Vec<f32> {
+        let mut features: Vec<f32> = self.queues.iter().chain(&self.entry_queues).map(|&q| q as f32).collect();
+        features.extend(self.waits);
+        features.extend((0..self.phase_count).map(|i| if self.phase == Some(i) { 1.0 } else { 0.0 }));
+        features
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvError {
+    InvalidAction { action: usize, action_count: usize },
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvError::InvalidAction { action, action_count } => {
+                write!(f, "action {} is out of range for {} phases", action, action_count)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EnvError {}
+
+// Shows whichever phase the environment was last told to.
+struct ExternalController {
+    action: Arc<AtomicUsize>,
+}
+
+impl SignalController for ExternalController {
+    fn name(&self) -> &str {
+        "external"
+    }
+
+    fn decide(&mut self, observation: &Observation) -> Option<Phase> {
+        observation.phases.get(self.action.load(Ordering::Relaxed)).cloned()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Totals {
+    delay: f32,
+    completed: usize,
+    stops: usize,
+}
+
+// A gym-style environment: each action picks the phase the signal should
+// move to, and the simulation then runs for one decision interval. Switching
+// still goes through the signal's amber and all-red.
+pub struct SignalEnv {
+    config: EnvConfig,
+    simulation: Simulation,
+    action: Arc<AtomicUsize>,
+    next_spawn: f32,
+    // Counted separately, since the simulation clock drifts from summing dt.
+    ticks: usize,
+    totals: Totals,
+}
+
+impl SignalEnv {
+    pub fn new(config: EnvConfig) -> Self {
+        let bounds = config.simulation.headless.bounds;
+        let mut env = Self {
+            config,
+            simulation: Simulation::new(bounds, 0),
+            action: Arc::new(AtomicUsize::new(0)),
+            next_spawn: 0.0,
+            ticks: 0,
+            totals: Totals::default(),
+        };
+        env.reset(0);
+        env
+    }
+
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+
+    pub fn simulation(&self) -> &Simulation {
+        &self.simulation
+    }
+
+    pub fn phases(&self) -> &[Phase] {
+        self.simulation.traffic_system().phases()
+    }
+
+    pub fn action_count(&self) -> usize {
+        self.phases().len()
+    }
+
+    pub fn reset(&mut self, seed: u64) -> EnvObservation {
+        self.simulation = Simulation::with_config(self.config.simulation.headless.bounds, seed, self.config.simulation.clone());
+        self.action = Arc::new(AtomicUsize::new(0));
+        self.simulation
+            .set_controller(Box::new(ExternalController {
+                action: Arc::clone(&self.action),
+            }))
+            .expect("the external controller only picks the signal's own phases");
+        self.simulation.spawner_mut().set_turning(self.config.turning.clone());
+        if let Some(profile) = &self.config.demand {
+            self.simulation.set_demand(profile.clone());
+        }
+        self.next_spawn = 0.0;
+        self.ticks = 0;
+        self.totals = Totals::default();
+        self.observe()
+    }
+
+    // An out-of-range action is rejected without advancing the simulation.
+    pub fn step(&mut self, action: usize) -> Result<(EnvObservation, f32, bool), EnvError> {
+        if action >= self.action_count() {
+            return Err(EnvError::InvalidAction {
+                action,
+                action_count: self.action_count(),
+            });
+        }
+        self.action.store(action, Ordering::Relaxed);
+
+        let dt = self.simulation.config().fixed_dt;
+        let steps = (self.config.decision_interval / dt).round().max(1.0) as usize;
+        for _ in 0..steps {
+            if self.config.demand.is_none() && self.simulation.time() >= self.next_spawn {
+                self.simulation.spawn_random();
+                self.next_spawn += self.config.spawn_interval;
+            }
+            self.simulation.step(dt);
+        }
+        self.ticks += steps;
+
+        let totals = self.current_totals();
+        let weights = self.config.reward;
+        let reward = -weights.delay * (totals.delay - self.totals.delay)
+            + weights.throughput * (totals.completed - self.totals.completed) as f32
+            - weights.stops * (totals.stops - self.totals.stops) as f32;
+        self.totals = totals;
+
+        let done = self.ticks as f64 * f64::from(dt) >= f64::from(self.config.episode_length) - 1e-6;
+        Ok((self.observe(), reward, done))
+    }
+
+    // Completed vehicles plus those still on the road or waiting to enter.
+    fn current_totals(&self) -> Totals {
+        let completed = self.simulation.statistics().total();
+        let vehicles = self.simulation.vehicles();
+
+        Totals {
+            delay: self.simulation.total_delay(),
+            completed: completed.completed,
+            stops: completed.total_stops + vehicles.iter().map(|v| v.stops).sum::<usize>(),
+        }
+    }
+
+    fn observe(&self) -> EnvObservation {
+        let waits = self.simulation.longest_waits();
+        let phase = self
+            .simulation
+            .active_phase()
+            .and_then(|active| self.phases().iter().position(|p| p.same_movements(active)));
+
+        EnvObservation {
+            time: self.simulation.time(),
+            queues: Origin::ALL.map(|o| self.simulation.lane_count(o)),
+            entry_queues: Origin::ALL.map(|o| self.simulation.entry_queue(o)),
+            waits: Origin::ALL.map(|o| waits[&o]),
+            phase,
+            phase_count: self.action_count(),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::app_config::*;
+use crate::control::controller::SignalController;
+use crate::control::plan::PlanError;
+use crate::manager::demand::DemandProfile;
+use crate::manager::scenario::{Scenario, ScenarioEvent};
+use crate::manager::simulation::Simulation;
+use crate::manager::turning::TurningMatrix;
+use crate::types::VehicleClass;
+
+// A simulation run without a window for `duration` simulated seconds. Traffic
+// comes from `demand` if set, otherwise a vehicle is offered at a random
+// approach every `spawn_interval` seconds.
+#[derive(Debug, Clone)]
+pub struct HeadlessRun {
+    pub seed: u64,
+    pub duration: f32,
+    pub spawn_interval: f32,
+    pub demand: Option<DemandProfile>,
+    pub turning: TurningMatrix,
+    // Keeps the spawner's default mix when unset.
+    pub class_mix: Option<Vec<(VehicleClass, f32)>>,
+    pub events: Vec<ScenarioEvent>,
+    pub config: SimConfig,
+}
+
+impl HeadlessRun {
+    pub fn new(seed: u64) -> Self {
+        Self::with_config(seed, SimConfig::default())
+    }
+
+    // Duration and spawn interval follow the configuration's headless section.
+    pub fn with_config(seed: u64, config: SimConfig) -> Self {
+        Self {
+            seed,
+            duration: config.headless.duration,
+            spawn_interval: config.headless.spawn_interval,
+            demand: None,
+            turning: TurningMatrix::uniform(),
+            class_mix: None,
+            events: Vec::new(),
+            config,
+        }
+    }
+
+    // Everything but the controller, which the caller passes to `run`.
+    pub fn from_scenario(scenario: &Scenario) -> Self {
+        Self {
+            seed: scenario.seed.unwrap_or(0),
+            duration: scenario.duration,
+            spawn_interval: scenario.spawn_interval,
+            demand: scenario.demand.clone(),
+            turning: scenario.turning.clone(),
+            class_mix: scenario.class_mix(),
+            events: scenario.events.clone(),
+            config: scenario.config.clone(),
+        }
+    }
+
+    // Fails if the controller or a scripted controller switch runs phases
+    // that conflict in this run's layout.
+    pub fn run(&self, controller: Box<dyn SignalController>) -> Result<Simulation, PlanError> {
+        let mut simulation = Simulation::with_config(self.config.headless.bounds, self.seed, self.config.clone());
+        simulation.set_controller(controller)?;
+        simulation.spawner_mut().set_turning(self.turning.clone());
+        if let Some(mix) = &self.class_mix {
+            simulation.spawner_mut().set_class_mix(mix.clone());
+        }
+        simulation.schedule(self.events.clone())?;
+        if let Some(profile) = &self.demand {
+            simulation.set_demand(profile.clone());
+        }
+
+        let dt = self.config.fixed_dt;
+        let steps = (self.duration / dt).round() as usize;
+        let mut next_spawn = 0.0;
+        for _ in 0..steps {
+            if simulation.demand().is_none() && simulation.time() >= next_spawn {
+                simulation.spawn_random();
+                next_spawn += self.spawn_interval;
+            }
+            simulation.step(dt);
+        }
+
+        Ok(simulation)
+    }
+}
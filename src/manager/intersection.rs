@@ -1,71 +1,234 @@
 use macroquad::prelude::*;
-use crate::entity::vehicle::Vehicle;
-use crate::entity::traffic_light::TrafficLightSystem;
+use crate::control::actuated::{ActuatedController, ActuatedParams};
+use crate::control::demand_based::{DemandController, DemandParams};
+use crate::control::fixed_time::FixedTimeController;
+use crate::control::max_pressure::{MaxPressureController, MaxPressureParams};
+use crate::control::controller::SignalController;
+use crate::control::plan::{PlanError, SignalPlan};
 use crate::manager::input::InputManager;
+use crate::manager::scenario::{ControllerSpec, Scenario};
+use crate::manager::simulation::Simulation;
 use crate::render::draw::Renderer;
+use crate::types::{Origin, Route, VehicleClass};
+
+// Controllers selectable with C before the loaded plans.
+const BUILTIN_CONTROLLERS: usize = 3;
 
 pub struct IntersectionManager {
-    vehicles: Vec<Vehicle>,
-    traffic_system: TrafficLightSystem,
-    center: (f32, f32),
+    simulation: Simulation,
+    accumulator: f32,
+    plans: Vec<SignalPlan>,
+    controller_index: usize,
+    show_detectors: bool,
 }
 
 impl IntersectionManager {
-    pub fn new() -> Self {
-        Self {
-            vehicles: Vec::with_capacity(50),
-            traffic_system: TrafficLightSystem::new(),
-            center: (screen_width() / 2.0, screen_height() / 2.0),
+    // Plans given on the command line take precedence over the scenario's
+    // controller. Every plan is checked against the configured layout up
+    // front, so cycling with C never installs one the signal cannot run.
+    pub fn new(seed: u64, mut plans: Vec<SignalPlan>, scenario: Scenario) -> Result<Self, PlanError> {
+        let start_with_plan = !plans.is_empty();
+        if let ControllerSpec::FixedTime(plan) = &scenario.controller {
+            plans.push(plan.clone());
+        }
+        plans.push(SignalPlan::four_phase(10.0, &scenario.config));
+
+        let mut manager = Self {
+            simulation: Simulation::with_config((screen_width(), screen_height()), seed, scenario.config.clone()),
+            accumulator: 0.0,
+            plans,
+            controller_index: 0,
+            show_detectors: true,
+        };
+
+        for plan in &manager.plans {
+            plan.validate_against(manager.simulation.traffic_system().conflicts())?;
+        }
+
+        manager.simulation.spawner_mut().set_turning(scenario.turning.clone());
+        if let Some(mix) = scenario.class_mix() {
+            manager.simulation.spawner_mut().set_class_mix(mix);
         }
+        if let Some(profile) = scenario.demand {
+            manager.simulation.set_demand(profile);
+        }
+        manager.simulation.schedule(scenario.events)?;
+
+        if start_with_plan {
+            manager.select_controller(BUILTIN_CONTROLLERS);
+        } else {
+            // Keeps the scenario's parameters; C then moves on from its slot.
+            manager.controller_index = match scenario.controller {
+                ControllerSpec::Demand(_) => 0,
+                ControllerSpec::Actuated(_) => 1,
+                ControllerSpec::MaxPressure(_) => 2,
+                ControllerSpec::FixedTime(_) => BUILTIN_CONTROLLERS,
+            };
+            manager.simulation.set_controller(scenario.controller.build())?;
+        }
+
+        Ok(manager)
+    }
+
+    // Index 0 is the demand-based controller, 1 the actuated one, 2 max-pressure,
+    // the rest are the fixed-time plans.
+    fn select_controller(&mut self, index: usize) {
+        self.controller_index = index % (self.plans.len() + BUILTIN_CONTROLLERS);
+
+        let config = self.simulation.config();
+        let controller: Box<dyn SignalController> = match self.controller_index {
+            0 => Box::new(DemandController::with_params(DemandParams::from_config(config))),
+            1 => Box::new(ActuatedController::with_params(ActuatedParams::from_config(config))),
+            2 => Box::new(MaxPressureController::with_params(MaxPressureParams::from_config(config))),
+            i => Box::new(FixedTimeController::new(self.plans[i - BUILTIN_CONTROLLERS].clone())),
+        };
+        self.simulation
+            .set_controller(controller)
+            .expect("plans are validated when the manager is created");
     }
 
     pub fn update(&mut self) {
-        self.center = (screen_width() / 2.0, screen_height() / 2.0);
-        let dt = get_frame_time();
-
-        InputManager::handle_input(&mut self.vehicles, self.center);
-        
-        self.traffic_system.update(dt, &self.vehicles, self.center);
-        let active_green = self.traffic_system.get_active_green();
-
-        let mut i = 0;
-        while i < self.vehicles.len() {
-            if self.vehicles[i].should_despawn() {
-                self.vehicles.swap_remove(i);
-            } else {
-                let safe = self.vehicles[i].is_safe_to_move(&self.vehicles, active_green, self.center);
-                
-                if safe {
-                    self.vehicles[i].update(self.center);
-                }
-                
-                i += 1;
-            }
+        self.simulation.set_bounds((screen_width(), screen_height()));
+
+        InputManager::handle_input(&mut self.simulation);
+
+        if InputManager::controller_cycle_requested() {
+            self.select_controller(self.controller_index + 1);
+        }
+
+        if InputManager::detector_overlay_toggled() {
+            self.show_detectors = !self.show_detectors;
+        }
+
+        self.accumulator += get_frame_time();
+
+        let (dt, max_steps) = (self.simulation.config().fixed_dt, self.simulation.config().max_steps_per_frame);
+        let mut steps = 0;
+        while self.accumulator >= dt && steps < max_steps {
+            self.simulation.step(dt);
+            self.accumulator -= dt;
+            steps += 1;
+        }
+
+        if steps == max_steps {
+            self.accumulator = self.accumulator.min(dt);
         }
     }
 
     pub fn draw(&self) {
-        Renderer::draw_environment(self.center);
+        let center = self.simulation.center();
+        let config = self.simulation.config();
+        let alpha = self.accumulator / config.fixed_dt;
+
+        Renderer::draw_environment(center, config);
+
+        if self.show_detectors {
+            for detector in self.simulation.traffic_system().detectors() {
+                detector.draw(center, config);
+            }
+        }
 
-        self.traffic_system.draw(self.center);
+        self.simulation.traffic_system().draw(center);
 
-        for vehicle in &self.vehicles {
-            vehicle.draw();
+        for vehicle in self.simulation.vehicles() {
+            vehicle.draw(alpha);
         }
-        
+
         draw_text(
-            &format!("Vehicles: {}", self.vehicles.len()), 
-            20.0, 
-            30.0, 
-            30.0, 
+            &format!("Vehicles: {}", self.simulation.vehicles().len()),
+            20.0,
+            30.0,
+            30.0,
             WHITE
         );
-        
+
+        draw_text(
+            &format!(
+                "Seed: {}  t={:.1}s  Demand: {}  Controller: {}  Phase: {}",
+                self.simulation.seed(),
+                self.simulation.time(),
+                self.simulation
+                    .demand()
+                    .map_or("manual".to_string(), |d| format!("{:.0} veh/h", d.total_rate(self.simulation.time()))),
+                self.simulation.traffic_system().controller_name(),
+                self.simulation.active_phase().map_or("clearance", |p| p.name.as_str())
+            ),
+            20.0,
+            55.0,
+            20.0,
+            GRAY
+        );
+
+        for (i, class) in VehicleClass::ALL.iter().enumerate() {
+            let stats = self.simulation.statistics().class(*class);
+            draw_text(
+                &format!(
+                    "{}: {} in / {} out, avg delay {:.1}s",
+                    class.name(),
+                    stats.spawned,
+                    stats.completed,
+                    stats.average_delay()
+                ),
+                20.0,
+                80.0 + i as f32 * 18.0,
+                18.0,
+                GRAY
+            );
+        }
+
+        let stats = self.simulation.statistics();
+        draw_text(
+            &format!(
+                "Avg delay  left {:.1}s  straight {:.1}s  right {:.1}s",
+                stats.route(Route::Left).average_delay(),
+                stats.route(Route::Straight).average_delay(),
+                stats.route(Route::Right).average_delay()
+            ),
+            20.0,
+            80.0 + VehicleClass::ALL.len() as f32 * 18.0,
+            18.0,
+            GRAY
+        );
+
+        let waits = self.simulation.longest_waits();
+        draw_text(
+            &format!(
+                "Longest wait  N {:.0}s  S {:.0}s  E {:.0}s  W {:.0}s",
+                waits[&Origin::North],
+                waits[&Origin::South],
+                waits[&Origin::East],
+                waits[&Origin::West]
+            ),
+            20.0,
+            80.0 + (VehicleClass::ALL.len() + 1) as f32 * 18.0,
+            18.0,
+            GRAY
+        );
+
+        draw_text(
+            &format!(
+                "Entry queue  N {}/{:.0}s  S {}/{:.0}s  E {}/{:.0}s  W {}/{:.0}s  avg wait {:.1}s",
+                self.simulation.entry_queue(Origin::North),
+                self.simulation.entry_wait(Origin::North),
+                self.simulation.entry_queue(Origin::South),
+                self.simulation.entry_wait(Origin::South),
+                self.simulation.entry_queue(Origin::East),
+                self.simulation.entry_wait(Origin::East),
+                self.simulation.entry_queue(Origin::West),
+                self.simulation.entry_wait(Origin::West),
+                stats.total().average_entry_wait()
+            ),
+            20.0,
+            80.0 + (VehicleClass::ALL.len() + 2) as f32 * 18.0,
+            18.0,
+            GRAY
+        );
+
         draw_text(
-            "Controls: Arrows to spawn, R for random, ESC to quit", 
-            20.0, 
-            screen_height() - 20.0, 
-            20.0, 
+            "Controls: Arrows to spawn, R for random, C to switch controller, D for detectors, ESC to quit",
+            20.0,
+            screen_height() - 20.0,
+            20.0,
             LIGHTGRAY
         );
     }
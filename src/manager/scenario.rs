@@ -0,0 +1,298 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::app_config::*;
+use crate::control::actuated::{ActuatedController, ActuatedParams};
+use crate::control::controller::SignalController;
+use crate::control::demand_based::{DemandController, DemandParams};
+use crate::control::fixed_time::FixedTimeController;
+use crate::control::max_pressure::{MaxPressureController, MaxPressureParams};
+use crate::control::phase::ConflictMatrix;
+use crate::control::plan::{SignalPlan, fill_clearances};
+use crate::manager::demand::DemandProfile;
+use crate::manager::turning::{TurningMatrix, TurningRatios};
+use crate::types::{Origin, VehicleClass};
+
+// The signal controller a scenario runs, with its parameters inline:
+// `kind = "actuated"` followed by e.g. `min_green = 6`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ControllerSpec {
+    Demand(DemandParams),
+    Actuated(ActuatedParams),
+    MaxPressure(MaxPressureParams),
+    FixedTime(SignalPlan),
+}
+
+impl Default for ControllerSpec {
+    fn default() -> Self {
+        ControllerSpec::Demand(DemandParams::default())
+    }
+}
+
+impl ControllerSpec {
+    // A controller of the given kind with the configuration's default
+    // parameters; fixed-time plans have no defaults.
+    pub fn from_config(kind: &str, config: &SimConfig) -> Option<Self> {
+        match kind {
+            "demand" => Some(ControllerSpec::Demand(DemandParams::from_config(config))),
+            "actuated" => Some(ControllerSpec::Actuated(ActuatedParams::from_config(config))),
+            "max-pressure" => Some(ControllerSpec::MaxPressure(MaxPressureParams::from_config(config))),
+            _ => None,
+        }
+    }
+
+    // A controller table as written in a scenario or a --params file, with
+    // the parameters it leaves out taken from the configuration.
+    pub fn from_table(mut table: toml::Table, config: &SimConfig) -> Result<Self, toml::de::Error> {
+        let defaults = table
+            .get("kind")
+            .and_then(|kind| kind.as_str())
+            .and_then(|kind| Self::from_config(kind, config));
+        match defaults {
+            Some(defaults) => {
+                let mut merged = toml::Table::try_from(defaults).expect("controller parameters always serialize");
+                merge(&mut merged, table);
+                merged.try_into()
+            }
+            None => {
+                if table.get("kind").and_then(|kind| kind.as_str()) == Some("fixed-time") {
+                    fill_clearances(&mut table, config);
+                }
+                table.try_into()
+            }
+        }
+    }
+
+    pub fn build(&self) -> Box<dyn SignalController> {
+        match self {
+            ControllerSpec::Demand(params) => Box::new(DemandController::with_params(params.clone())),
+            ControllerSpec::Actuated(params) => Box::new(ActuatedController::with_params(params.clone())),
+            ControllerSpec::MaxPressure(params) => Box::new(MaxPressureController::with_params(params.clone())),
+            ControllerSpec::FixedTime(plan) => Box::new(FixedTimeController::new(plan.clone())),
+        }
+    }
+
+    // Plans are checked against the conflicts of the scenario's own layout.
+    pub fn validate(&self, config: &SimConfig) -> Result<(), String> {
+        match self {
+            ControllerSpec::Demand(params) => params.validate(),
+            ControllerSpec::Actuated(params) => params.validate(),
+            ControllerSpec::MaxPressure(params) => params.validate(),
+            ControllerSpec::FixedTime(plan) => plan
+                .validate_against(&ConflictMatrix::from_geometry(config))
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+// Something that happens `at` seconds into the run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioEvent {
+    pub at: f32,
+    #[serde(flatten)]
+    pub action: EventAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum EventAction {
+    // A burst of arrivals on one approach, e.g. a platoon released upstream.
+    Spawn { origin: Origin, count: usize },
+    Controller { controller: ControllerSpec },
+    Turning { origin: Origin, ratios: TurningRatios },
+    // Replaces the demand profile; its period starts stay absolute.
+    Demand { demand: DemandProfile },
+}
+
+// A whole experiment in one file. Everything is optional; what is left out
+// keeps the defaults of a plain run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scenario {
+    pub name: String,
+    // A window run picks a random seed when none is given, a headless run 0.
+    pub seed: Option<u64>,
+    // Only headless runs stop; the window runs until it is closed.
+    pub duration: f32,
+    // Seconds between random arrivals when there is no demand profile.
+    pub spawn_interval: f32,
+    pub controller: ControllerSpec,
+    pub demand: Option<DemandProfile>,
+    pub turning: TurningMatrix,
+    // Relative weights per class; classes left out never spawn.
+    pub class_mix: Option<HashMap<VehicleClass, f32>>,
+    // Geometry, vehicle dynamics and the time step.
+    pub config: SimConfig,
+    pub events: Vec<ScenarioEvent>,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        let config = SimConfig::default();
+        Self {
+            name: String::new(),
+            seed: None,
+            duration: config.headless.duration,
+            spawn_interval: config.headless.spawn_interval,
+            controller: ControllerSpec::default(),
+            demand: None,
+            turning: TurningMatrix::uniform(),
+            class_mix: None,
+            config,
+            events: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ScenarioError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::Io(e) => write!(f, "cannot read scenario: {}", e),
+            ScenarioError::Parse(e) => write!(f, "cannot parse scenario: {}", e),
+            ScenarioError::Invalid(msg) => write!(f, "invalid scenario: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+impl Scenario {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        Self::load_with(path, Ok)
+    }
+
+    pub fn load_with(
+        path: impl AsRef<Path>,
+        configure: impl FnOnce(SimConfig) -> Result<SimConfig, ConfigError>,
+    ) -> Result<Self, ScenarioError> {
+        let text = fs::read_to_string(path).map_err(ScenarioError::Io)?;
+        Self::from_toml_with(&text, configure)
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, ScenarioError> {
+        Self::from_toml_with(text, Ok)
+    }
+
+    // Like `from_toml`, with the scenario's configuration passed through
+    // `configure` first, e.g. to apply command-line overrides. Controller
+    // parameters the file leaves out come from the final configuration.
+    pub fn from_toml_with(
+        text: &str,
+        configure: impl FnOnce(SimConfig) -> Result<SimConfig, ConfigError>,
+    ) -> Result<Self, ScenarioError> {
+        let mut table: toml::Table = toml::from_str(text).map_err(ScenarioError::Parse)?;
+        let config: SimConfig = match table.remove("config") {
+            Some(config) => config.try_into().map_err(ScenarioError::Parse)?,
+            None => SimConfig::default(),
+        };
+        let config = configure(config).map_err(|e| ScenarioError::Invalid(format!("config: {}", e)))?;
+
+        let controller = |value: toml::Value| -> Result<toml::Value, ScenarioError> {
+            let toml::Value::Table(table) = value else {
+                return Ok(value);
+            };
+            let spec = ControllerSpec::from_table(table, &config).map_err(ScenarioError::Parse)?;
+            Ok(toml::Value::try_from(spec).expect("controllers always serialize"))
+        };
+        // Without a controller the run keeps the demand-based default.
+        let value = table
+            .remove("controller")
+            .unwrap_or_else(|| toml::Value::Table(toml::Table::from_iter([("kind".into(), "demand".into())])));
+        table.insert("controller".into(), controller(value)?);
+        if let Some(toml::Value::Array(events)) = table.get_mut("events") {
+            for event in events.iter_mut().filter_map(|e| e.as_table_mut()) {
+                if let Some(value) = event.remove("controller") {
+                    event.insert("controller".into(), controller(value)?);
+                }
+            }
+        }
+
+        // Run lengths left out follow the configuration's headless defaults.
+        table.entry("duration").or_insert(config.headless.duration.into());
+        table.entry("spawn_interval").or_insert(config.headless.spawn_interval.into());
+        table.insert("config".into(), toml::Value::try_from(&config).expect("configurations always serialize"));
+
+        let scenario: Scenario = table.try_into().map_err(ScenarioError::Parse)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    pub fn validate(&self) -> Result<(), ScenarioError> {
+        let invalid = |context: &str, e: String| ScenarioError::Invalid(format!("{}: {}", context, e));
+
+        let positive = [("duration", self.duration), ("spawn_interval", self.spawn_interval)];
+        if let Some((name, value)) = positive.iter().find(|(_, v)| !(v.is_finite() && *v > 0.0)) {
+            return Err(ScenarioError::Invalid(format!("{} must be positive, got {}", name, value)));
+        }
+
+        self.config.validate().map_err(|e| invalid("config", e.to_string()))?;
+        self.controller.validate(&self.config).map_err(|e| invalid("controller", e))?;
+        if let Some(demand) = &self.demand {
+            demand.validate().map_err(|e| invalid("demand", e.to_string()))?;
+        }
+        self.turning.validate().map_err(|e| invalid("turning", e.to_string()))?;
+
+        if let Some(mix) = &self.class_mix {
+            if mix.values().any(|w| !w.is_finite() || *w < 0.0) {
+                return Err(invalid("class_mix", "weights must be finite and non-negative".into()));
+            }
+            if mix.values().sum::<f32>() <= 0.0 {
+                return Err(invalid("class_mix", "at least one class needs a positive weight".into()));
+            }
+        }
+
+        for (i, event) in self.events.iter().enumerate() {
+            let context = format!("event {}", i + 1);
+            if !event.at.is_finite() || event.at < 0.0 {
+                return Err(invalid(&context, format!("cannot happen at {}s", event.at)));
+            }
+            match &event.action {
+                EventAction::Spawn { .. } => {}
+                EventAction::Controller { controller } => controller.validate(&self.config).map_err(|e| invalid(&context, e))?,
+                EventAction::Turning { origin, ratios } => {
+                    TurningMatrix::uniform()
+                        .with(*origin, *ratios)
+                        .map_err(|e| invalid(&context, e.to_string()))?;
+                }
+                EventAction::Demand { demand } => demand.validate().map_err(|e| invalid(&context, e.to_string()))?,
+            }
+        }
+
+        Ok(())
+    }
+
+    // The class weights in `VehicleClass::ALL` order, if the scenario sets them.
+    pub fn class_mix(&self) -> Option<Vec<(VehicleClass, f32)>> {
+        let mix = self.class_mix.as_ref()?;
+        Some(
+            VehicleClass::ALL
+                .into_iter()
+                .map(|class| (class, mix.get(&class).copied().unwrap_or(0.0)))
+                .collect(),
+        )
+    }
+}
+
+// Overlays `overrides` on `base`, merging tables such as a partial
+// `max_green` map key by key instead of replacing them.
+fn merge(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(value)) => merge(base, value),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use rand::rngs::StdRng;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use rand::{Rng, SeedableRng};
+
+use crate::app_config::SimConfig;
+use crate::control::controller::{oldest_waits, SignalController};
+use crate::control::phase::Phase;
+use crate::control::plan::PlanError;
+use crate::entity::traffic_light::TrafficLightSystem;
+use crate::entity::vehicle::Vehicle;
+use crate::manager::demand::{DemandGenerator, DemandProfile};
+use crate::manager::scenario::{EventAction, ScenarioEvent};
+use crate::manager::spawner::Spawner;
+use crate::manager::statistics::Statistics;
+use crate::types::{Movement, Origin, SignalAspect};
+
+pub struct Simulation {
+    vehicles: Vec<Vehicle>,
+    traffic_system: TrafficLightSystem,
+    spawner: Spawner,
+    demand: Option<DemandGenerator>,
+    // Vehicles that have arrived but found no room to enter yet, per approach.
+    entry_queues: HashMap<Origin, VecDeque<Vehicle>>,
+    // Scripted events still to come, earliest first.
+    events: VecDeque<ScenarioEvent>,
+    statistics: Statistics,
+    bounds: (f32, f32),
+    time: f32,
+    seed: u64,
+    rng: StdRng,
+    config: Arc<SimConfig>,
+}
+
+impl Simulation {
+    pub fn new(bounds: (f32, f32), seed: u64) -> Self {
+        Self::with_config(bounds, seed, SimConfig::default())
+    }
+
+    // The configuration is shared with the signal, the spawner and every vehicle.
+    pub fn with_config(bounds: (f32, f32), seed: u64, config: SimConfig) -> Self {
+        let config = Arc::new(config);
+
+        Self {
+            vehicles: Vec::with_capacity(50),
+            traffic_system: TrafficLightSystem::new(Arc::clone(&config)),
+            spawner: Spawner::new(Arc::clone(&config)),
+            demand: None,
+            entry_queues: HashMap::new(),
+            events: VecDeque::new(),
+            statistics: Statistics::new(),
+            bounds,
+            time: 0.0,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &SimConfig {
+        &self.config
+    }
+
+    pub fn step(&mut self, dt: f32) {
+        self.time += dt;
+        let center = self.center();
+
+        while let Some(event) = self.events.pop_front_if(|e| e.at <= self.time) {
+            self.apply(event.action);
+        }
+
+        for queue in self.entry_queues.values_mut() {
+            for vehicle in queue.iter_mut() {
+                vehicle.entry_wait += dt;
+            }
+        }
+
+        for origin in Origin::ALL {
+            self.release_entries(origin);
+        }
+
+        if let Some(demand) = self.demand.as_mut() {
+            for origin in demand.arrivals(self.time, &mut self.rng) {
+                self.spawn(origin);
+            }
+        }
+
+        self.traffic_system.update(dt, self.time, &self.vehicles, center);
+
+        let bounds = self.bounds;
+        let statistics = &mut self.statistics;
+        self.vehicles.retain(|v| {
+            let despawn = v.should_despawn(bounds);
+            if despawn {
+                statistics.record_exit(v);
+            }
+            !despawn
+        });
+
+        // Whether a vehicle yields is settled by the aspect it crosses its stop
+        // line on. The amber that ends a permissive interval gives no priority,
+        // so a vehicle that was yielding keeps yielding through it.
+        for vehicle in self.vehicles.iter_mut() {
+            if !vehicle.turned && vehicle.distance_to_stop_line(center) > 0.0 {
+                vehicle.yielding = match self.traffic_system.aspect(vehicle.movement()) {
+                    SignalAspect::Permissive => true,
+                    SignalAspect::Amber => vehicle.yielding,
+                    SignalAspect::Green | SignalAspect::Red => false,
+                };
+            }
+        }
+
+        let signal = &self.traffic_system;
+        let accelerations: Vec<f32> = self
+            .vehicles
+            .iter()
+            .map(|v| {
+                let yield_gap = v.yield_gap(&self.vehicles, signal.conflicts(), |m| signal.aspect(m), center);
+                v.acceleration(&self.vehicles, signal.aspect(v.movement()), yield_gap, center)
+            })
+            .collect();
+
+        for (vehicle, acceleration) in self.vehicles.iter_mut().zip(accelerations) {
+            vehicle.prev_pos = vehicle.pos;
+            vehicle.prev_heading = vehicle.heading;
+            vehicle.update(dt, acceleration, center);
+        }
+    }
+
+    // The new vehicle joins the approach's entry queue and enters as soon as
+    // the lane has room and its spawn point is clear.
+    pub fn spawn(&mut self, origin: Origin) {
+        let pos = self.spawn_point(origin);
+        let vehicle = self.spawner.create(&mut self.rng, pos, origin);
+
+        self.statistics.record_arrival(vehicle.movement());
+        self.entry_queues.entry(origin).or_default().push_back(vehicle);
+        self.release_entries(origin);
+    }
+
+    fn release_entries(&mut self, origin: Origin) {
+        let pos = self.spawn_point(origin);
+        let mut lane_count = self.lane_count(origin);
+        let capacity = self.config.lane_capacity();
+        let Some(queue) = self.entry_queues.get_mut(&origin) else {
+            return;
+        };
+
+        while lane_count < capacity {
+            // The window may have been resized since the vehicle arrived.
+            let Some(vehicle) = queue.front_mut() else {
+                return;
+            };
+            vehicle.pos = pos;
+            vehicle.prev_pos = pos;
+
+            if !self.spawner.try_spawn(&mut self.vehicles, queue) {
+                return;
+            }
+            if let Some(vehicle) = self.vehicles.last() {
+                self.statistics.record_spawn(vehicle.class, vehicle.route);
+            }
+            lane_count += 1;
+        }
+    }
+
+    // Replaces any events still pending. Controller switches are checked
+    // against this simulation's layout up front.
+    pub fn schedule(&mut self, mut events: Vec<ScenarioEvent>) -> Result<(), PlanError> {
+        for event in &events {
+            if let EventAction::Controller { controller } = &event.action {
+                controller.build().validate(self.traffic_system.conflicts())?;
+            }
+        }
+
+        events.sort_by(|a, b| a.at.total_cmp(&b.at));
+        self.events = events.into();
+        Ok(())
+    }
+
+    fn apply(&mut self, action: EventAction) {
+        match action {
+            EventAction::Spawn { origin, count } => {
+                for _ in 0..count {
+                    self.spawn(origin);
+                }
+            }
+            EventAction::Controller { controller } => self
+                .set_controller(controller.build())
+                .expect("controller events are validated when scheduled"),
+            EventAction::Turning { origin, ratios } => {
+                let turning = self.spawner.turning().clone().with(origin, ratios);
+                self.spawner.set_turning(turning.expect("scenario events are validated on load"));
+            }
+            EventAction::Demand { demand } => self.set_demand(demand),
+        }
+    }
+
+    pub fn entry_queue(&self, origin: Origin) -> usize {
+        self.entry_queues.get(&origin).map_or(0, VecDeque::len)
+    }
+
+    // How long the first vehicle in the approach's entry queue has waited.
+    pub fn entry_wait(&self, origin: Origin) -> f32 {
+        self.entry_queues
+            .get(&origin)
+            .and_then(VecDeque::front)
+            .map_or(0.0, |v| v.entry_wait)
+    }
+
+    // Delay so far of every vehicle that has arrived: completed, still on the
+    // road, or waiting to enter.
+    pub fn total_delay(&self) -> f32 {
+        let completed = self.statistics.total().total_delay;
+        let on_road: f32 = self.vehicles.iter().map(|v| v.delay + v.entry_wait).sum();
+        let entering: f32 = self.queued_vehicles().map(|v| v.entry_wait).sum();
+        completed + on_road + entering
+    }
+
+    // Vehicles that have shown up at an approach, whether or not they entered.
+    pub fn arrived(&self) -> usize {
+        self.statistics.total().spawned + self.queued_vehicles().count()
+    }
+
+    // In `Origin::ALL` order, so sums over the queues are the same every run.
+    pub fn queued_vehicles(&self) -> impl Iterator<Item = &Vehicle> {
+        Origin::ALL
+            .into_iter()
+            .flat_map(|origin| self.entry_queues.get(&origin).into_iter().flatten())
+    }
+
+    pub fn spawn_random(&mut self) {
+        let origin = match self.rng.gen_range(0..4) {
+            0 => Origin::North,
+            1 => Origin::South,
+            2 => Origin::East,
+            _ => Origin::West,
+        };
+        self.spawn(origin);
+    }
+
+    pub fn lane_count(&self, origin: Origin) -> usize {
+        self.vehicles
+            .iter()
+            .filter(|v| v.origin == origin && !v.turned)
+            .count()
+    }
+
+    fn spawn_point(&self, origin: Origin) -> (f32, f32) {
+        let center = self.center();
+        let (lane, margin) = (self.config.lane_center, self.config.spawn_margin);
+
+        match origin {
+            Origin::North => (center.0 - lane, -margin),
+            Origin::South => (center.0 + lane, self.bounds.1 + margin),
+            Origin::East => (-margin, center.1 + lane),
+            Origin::West => (self.bounds.0 + margin, center.1 - lane),
+        }
+    }
+
+    pub fn set_bounds(&mut self, bounds: (f32, f32)) {
+        self.bounds = bounds;
+    }
+
+    pub fn bounds(&self) -> (f32, f32) {
+        self.bounds
+    }
+
+    pub fn center(&self) -> (f32, f32) {
+        (self.bounds.0 / 2.0, self.bounds.1 / 2.0)
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    pub fn vehicles(&self) -> &[Vehicle] {
+        &self.vehicles
+    }
+
+    pub fn statistics(&self) -> &Statistics {
+        &self.statistics
+    }
+
+    // Arrival rate per movement in vehicles per second since the start.
+    pub fn measured_flows(&self) -> HashMap<Movement, f32> {
+        Movement::all()
+            .map(|m| {
+                let flow = if self.time > 0.0 { self.statistics.arrivals(m) as f32 / self.time } else { 0.0 };
+                (m, flow)
+            })
+            .collect()
+    }
+
+    // Vehicles then arrive on their own.
+    pub fn set_demand(&mut self, profile: DemandProfile) {
+        self.demand = Some(DemandGenerator::new(profile));
+    }
+
+    pub fn demand(&self) -> Option<&DemandProfile> {
+        self.demand.as_ref().map(DemandGenerator::profile)
+    }
+
+    pub fn spawner_mut(&mut self) -> &mut Spawner {
+        &mut self.spawner
+    }
+
+    pub fn traffic_system(&self) -> &TrafficLightSystem {
+        &self.traffic_system
+    }
+
+    pub fn set_controller(&mut self, controller: Box<dyn SignalController>) -> Result<(), PlanError> {
+        self.traffic_system.set_controller(controller)
+    }
+
+    pub fn active_phase(&self) -> Option<&Phase> {
+        self.traffic_system.active_phase()
+    }
+
+    pub fn longest_waits(&self) -> HashMap<Origin, f32> {
+        let mut waits: HashMap<Origin, f32> = Origin::ALL.into_iter().map(|o| (o, 0.0)).collect();
+        for (movement, wait) in oldest_waits(&self.vehicles) {
+            let longest = waits.entry(movement.origin).or_default();
+            *longest = longest.max(wait);
+        }
+        waits
+    }
+
+    pub fn aspect(&self, movement: Movement) -> SignalAspect {
+        self.traffic_system.aspect(movement)
+    }
+}
@@ -1,34 +1,94 @@
-use macroquad::prelude::*;
-use crate::app_config::*;
+use macroquad::color::Color;
+use rand::Rng;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use crate::app_config::SimConfig;
 use crate::entity::vehicle::Vehicle;
-use crate::types::{Origin, Route};
+use crate::manager::turning::TurningMatrix;
+use crate::types::{Origin, Route, VehicleClass};
 use crate::render::palette::*;
 
-pub struct Spawner;
+pub struct Spawner {
+    next_id: usize,
+    class_mix: Vec<(VehicleClass, f32)>,
+    turning: TurningMatrix,
+    config: Arc<SimConfig>,
+}
 
 impl Spawner {
-    pub fn try_spawn(vehicles: &mut Vec<Vehicle>, pos: (f32, f32), origin: Origin, speed: (f32, f32)) {
-        let is_safe = !vehicles.iter().any(|v| {
-            let dx = v.pos.0 - pos.0;
-            let dy = v.pos.1 - pos.1;
-            (dx * dx + dy * dy) < SPAWN_DISTANCE_CHECK * SPAWN_DISTANCE_CHECK
-        });
-
-        if is_safe {
-            let (route, color) = Self::get_random_attributes();
-            vehicles.push(Vehicle::new(pos, speed, origin, route, color));
+    pub fn new(config: Arc<SimConfig>) -> Self {
+        Self {
+            next_id: 0,
+            class_mix: VehicleClass::ALL.iter().map(|&c| (c, c.spec(&config).spawn_weight)).collect(),
+            turning: TurningMatrix::uniform(),
+            config,
         }
     }
 
-    fn get_random_attributes() -> (Route, Color) {
-        let route = match rand::gen_range(0, 3) {
-            0 => Route::Right,
-            1 => Route::Left,
-            _ => Route::Straight,
+    pub fn class_mix(&self) -> &[(VehicleClass, f32)] {
+        &self.class_mix
+    }
+
+    pub fn set_class_mix(&mut self, mix: Vec<(VehicleClass, f32)>) {
+        self.class_mix = mix;
+    }
+
+    pub fn turning(&self) -> &TurningMatrix {
+        &self.turning
+    }
+
+    pub fn set_turning(&mut self, turning: TurningMatrix) {
+        self.turning = turning;
+    }
+
+    // A new vehicle with its class, route and color drawn; it is not on the
+    // road until `try_spawn` places it.
+    pub fn create<R: Rng>(&mut self, rng: &mut R, pos: (f32, f32), origin: Origin) -> Vehicle {
+        let class = self.choose_class(rng);
+        let (route, color) = self.get_random_attributes(rng, origin, class);
+        let vehicle = Vehicle::new(self.next_id, class, pos, origin, route, color, Arc::clone(&self.config));
+        self.next_id += 1;
+        vehicle
+    }
+
+    // Moves the first vehicle of an entry queue onto the road if its spawn
+    // point is clear.
+    pub fn try_spawn(&self, vehicles: &mut Vec<Vehicle>, queue: &mut VecDeque<Vehicle>) -> bool {
+        let Some(vehicle) = queue.front() else {
+            return false;
         };
 
-        let color_idx = rand::gen_range(0, CAR_COLORS.len());
-        let color = CAR_COLORS[color_idx];
+        let clearance = vehicle.obb().inflated(self.config.spawn_distance_check);
+        if vehicles.iter().any(|v| v.obb().overlaps(&clearance)) {
+            return false;
+        }
+
+        vehicles.extend(queue.pop_front());
+        true
+    }
+
+    fn choose_class<R: Rng>(&self, rng: &mut R) -> VehicleClass {
+        let total: f32 = self.class_mix.iter().map(|(_, w)| w).sum();
+        let mut roll = rng.gen_range(0.0..total.max(f32::EPSILON));
+
+        for &(class, weight) in &self.class_mix {
+            if roll < weight {
+                return class;
+            }
+            roll -= weight;
+        }
+
+        VehicleClass::Car
+    }
+
+    fn get_random_attributes<R: Rng>(&self, rng: &mut R, origin: Origin, class: VehicleClass) -> (Route, Color) {
+        let route = self.turning.choose(origin, rng);
+
+        let color_idx = rng.gen_range(0..CAR_COLORS.len());
+        let color = match class {
+            VehicleClass::Bus => COLOR_BUS,
+            _ => CAR_COLORS[color_idx],
+        };
 
         (route, color)
     }
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use crate::entity::vehicle::Vehicle;
+use crate::types::{Movement, Route, VehicleClass};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ClassStats {
+    pub spawned: usize,
+    pub completed: usize,
+    pub total_travel_time: f32,
+    pub total_delay: f32,
+    pub total_stops: usize,
+    pub total_entry_wait: f32,
+}
+
+impl ClassStats {
+    pub fn average_travel_time(&self) -> f32 {
+        if self.completed == 0 { 0.0 } else { self.total_travel_time / self.completed as f32 }
+    }
+
+    // Includes the time spent waiting to enter.
+    pub fn average_delay(&self) -> f32 {
+        if self.completed == 0 { 0.0 } else { self.total_delay / self.completed as f32 }
+    }
+
+    pub fn average_entry_wait(&self) -> f32 {
+        if self.completed == 0 { 0.0 } else { self.total_entry_wait / self.completed as f32 }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Statistics {
+    per_class: HashMap<VehicleClass, ClassStats>,
+    per_route: HashMap<Route, ClassStats>,
+    arrivals: HashMap<Movement, usize>,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A vehicle showing up at an approach, whether or not it can enter yet.
+    pub fn record_arrival(&mut self, movement: Movement) {
+        *self.arrivals.entry(movement).or_default() += 1;
+    }
+
+    pub fn record_spawn(&mut self, class: VehicleClass, route: Route) {
+        self.per_class.entry(class).or_default().spawned += 1;
+        self.per_route.entry(route).or_default().spawned += 1;
+    }
+
+    pub fn record_exit(&mut self, vehicle: &Vehicle) {
+        for stats in [
+            self.per_class.entry(vehicle.class).or_default(),
+            self.per_route.entry(vehicle.route).or_default(),
+        ] {
+            stats.completed += 1;
+            stats.total_travel_time += vehicle.age;
+            stats.total_delay += vehicle.delay + vehicle.entry_wait;
+            stats.total_stops += vehicle.stops;
+            stats.total_entry_wait += vehicle.entry_wait;
+        }
+    }
+
+    pub fn class(&self, class: VehicleClass) -> ClassStats {
+        self.per_class.get(&class).copied().unwrap_or_default()
+    }
+
+    // Left turns are reported on their own since they wait for gaps.
+    pub fn route(&self, route: Route) -> ClassStats {
+        self.per_route.get(&route).copied().unwrap_or_default()
+    }
+
+    pub fn arrivals(&self, movement: Movement) -> usize {
+        self.arrivals.get(&movement).copied().unwrap_or(0)
+    }
+
+    // Summed in `VehicleClass::ALL` order: a map's order changes from one
+    // process to the next, and with it the rounding of the float totals.
+    pub fn total(&self) -> ClassStats {
+        VehicleClass::ALL.map(|c| self.class(c)).iter().fold(ClassStats::default(), |acc, s| ClassStats {
+            spawned: acc.spawned + s.spawned,
+            completed: acc.completed + s.completed,
+            total_travel_time: acc.total_travel_time + s.total_travel_time,
+            total_delay: acc.total_delay + s.total_delay,
+            total_stops: acc.total_stops + s.total_stops,
+            total_entry_wait: acc.total_entry_wait + s.total_entry_wait,
+        })
+    }
+}
@@ -0,0 +1,135 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::types::{Origin, Route};
+
+// Relative shares of the routes taken from one approach; they need not add
+// up to one.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TurningRatios {
+    pub left: f32,
+    pub straight: f32,
+    pub right: f32,
+}
+
+impl Default for TurningRatios {
+    fn default() -> Self {
+        Self::new(1.0, 1.0, 1.0)
+    }
+}
+
+impl TurningRatios {
+    pub fn new(left: f32, straight: f32, right: f32) -> Self {
+        Self { left, straight, right }
+    }
+
+    pub fn share(&self, route: Route) -> f32 {
+        let total = self.left + self.straight + self.right;
+        let weight = match route {
+            Route::Left => self.left,
+            Route::Straight => self.straight,
+            Route::Right => self.right,
+        };
+        weight / total
+    }
+
+    pub fn choose<R: Rng>(&self, rng: &mut R) -> Route {
+        let mut roll = rng.gen_range(0.0..1.0);
+        for route in Route::ALL {
+            let share = self.share(route);
+            if roll < share {
+                return route;
+            }
+            roll -= share;
+        }
+        Route::Straight
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let weights = [self.left, self.straight, self.right];
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err("turning ratios must be finite and non-negative".into());
+        }
+        if weights.iter().sum::<f32>() <= 0.0 {
+            return Err("turning ratios need at least one positive share".into());
+        }
+        Ok(())
+    }
+}
+
+// Turning ratios per approach. Approaches that are left out split evenly.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TurningMatrix {
+    ratios: HashMap<Origin, TurningRatios>,
+}
+
+#[derive(Debug)]
+pub enum TurningError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for TurningError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TurningError::Io(e) => write!(f, "cannot read turning matrix: {}", e),
+            TurningError::Parse(e) => write!(f, "cannot parse turning matrix: {}", e),
+            TurningError::Invalid(msg) => write!(f, "invalid turning matrix: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for TurningError {}
+
+impl TurningMatrix {
+    pub fn uniform() -> Self {
+        Self::default()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TurningError> {
+        let text = fs::read_to_string(path).map_err(TurningError::Io)?;
+        Self::from_toml(&text)
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, TurningError> {
+        let matrix: TurningMatrix = toml::from_str(text).map_err(TurningError::Parse)?;
+        matrix.validate()?;
+        Ok(matrix)
+    }
+
+    pub fn validate(&self) -> Result<(), TurningError> {
+        for (origin, ratios) in &self.ratios {
+            ratios
+                .validate()
+                .map_err(|e| TurningError::Invalid(format!("{:?}: {}", origin, e)))?;
+        }
+        Ok(())
+    }
+
+    pub fn ratios(&self, origin: Origin) -> TurningRatios {
+        self.ratios.get(&origin).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, origin: Origin, ratios: TurningRatios) -> Result<(), TurningError> {
+        ratios
+            .validate()
+            .map_err(|e| TurningError::Invalid(format!("{:?}: {}", origin, e)))?;
+        self.ratios.insert(origin, ratios);
+        Ok(())
+    }
+
+    pub fn with(mut self, origin: Origin, ratios: TurningRatios) -> Result<Self, TurningError> {
+        self.set(origin, ratios)?;
+        Ok(self)
+    }
+
+    pub fn choose<R: Rng>(&self, origin: Origin, rng: &mut R) -> Route {
+        self.ratios(origin).choose(rng)
+    }
+}
@@ -0,0 +1,191 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::thread;
+
+#[derive(Debug, Clone)]
+pub struct Gene {
+    pub name: String,
+    pub min: f32,
+    pub max: f32,
+    // Seeded into the first generation, so the search never does worse than
+    // the starting point.
+    pub initial: f32,
+}
+
+impl Gene {
+    pub fn new(name: impl Into<String>, min: f32, max: f32, initial: f32) -> Self {
+        Self {
+            name: name.into(),
+            min,
+            max,
+            initial: initial.clamp(min, max),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneticParams {
+    pub population: usize,
+    pub generations: usize,
+    // The best individuals are copied unchanged into the next generation.
+    pub elite: usize,
+    pub tournament: usize,
+    pub crossover_rate: f32,
+    pub mutation_rate: f32,
+    // Standard deviation of a mutation as a fraction of the gene's range.
+    pub mutation_scale: f32,
+    pub seed: u64,
+    pub threads: usize,
+}
+
+impl Default for GeneticParams {
+    fn default() -> Self {
+        Self {
+            population: 24,
+            generations: 20,
+            elite: 2,
+            tournament: 3,
+            crossover_rate: 0.9,
+            mutation_rate: 0.2,
+            mutation_scale: 0.1,
+            seed: 0,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Generation {
+    pub index: usize,
+    pub best: f32,
+    pub mean: f32,
+    pub best_genes: Vec<f32>,
+}
+
+// A real-coded genetic algorithm that minimizes `cost` over the genes' box.
+// Costs of a generation are evaluated in parallel, so `cost` typically runs
+// one or more headless simulations.
+pub struct GeneticSearch {
+    params: GeneticParams,
+    genes: Vec<Gene>,
+}
+
+impl GeneticSearch {
+    pub fn new(genes: Vec<Gene>, params: GeneticParams) -> Self {
+        Self { params, genes }
+    }
+
+    pub fn genes(&self) -> &[Gene] {
+        &self.genes
+    }
+
+    pub fn run<F>(&self, cost: F, mut on_generation: impl FnMut(&Generation)) -> (Vec<f32>, f32)
+    where
+        F: Fn(&[f32]) -> f32 + Sync,
+    {
+        let mut rng = StdRng::seed_from_u64(self.params.seed);
+        let size = self.params.population.max(2);
+
+        let mut population: Vec<Vec<f32>> = vec![self.genes.iter().map(|g| g.initial).collect()];
+        while population.len() < size {
+            population.push(self.genes.iter().map(|g| rng.gen_range(g.min..=g.max)).collect());
+        }
+        let mut costs = self.evaluate(&population, &cost);
+
+        for index in 0..=self.params.generations {
+            let mut order: Vec<usize> = (0..population.len()).collect();
+            order.sort_by(|&a, &b| costs[a].total_cmp(&costs[b]));
+
+            on_generation(&Generation {
+                index,
+                best: costs[order[0]],
+                mean: costs.iter().sum::<f32>() / costs.len() as f32,
+                best_genes: population[order[0]].clone(),
+            });
+
+            if index == self.params.generations {
+                return (population[order[0]].clone(), costs[order[0]]);
+            }
+
+            let elite = self.params.elite.min(size);
+            let mut next: Vec<Vec<f32>> = order[..elite].iter().map(|&i| population[i].clone()).collect();
+            let mut next_costs: Vec<f32> = order[..elite].iter().map(|&i| costs[i]).collect();
+
+            let mut children = Vec::with_capacity(size - elite);
+            while children.len() < size - elite {
+                let a = &population[self.select(&costs, &mut rng)];
+                let b = &population[self.select(&costs, &mut rng)];
+                let mut child = if rng.r#gen::<f32>() < self.params.crossover_rate {
+                    self.crossover(a, b, &mut rng)
+                } else {
+                    a.clone()
+                };
+                self.mutate(&mut child, &mut rng);
+                children.push(child);
+            }
+
+            next_costs.extend(self.evaluate(&children, &cost));
+            next.extend(children);
+            population = next;
+            costs = next_costs;
+        }
+
+        unreachable!("the last generation returns")
+    }
+
+    fn evaluate<F>(&self, individuals: &[Vec<f32>], cost: &F) -> Vec<f32>
+    where
+        F: Fn(&[f32]) -> f32 + Sync,
+    {
+        let threads = self.params.threads.max(1);
+        let chunk = individuals.len().div_ceil(threads).max(1);
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = individuals
+                .chunks(chunk)
+                .map(|chunk| scope.spawn(move || chunk.iter().map(|genes| cost(genes)).collect::<Vec<f32>>()))
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().expect("cost evaluation panicked"))
+                .collect()
+        })
+    }
+
+    fn select(&self, costs: &[f32], rng: &mut StdRng) -> usize {
+        (0..self.params.tournament.max(1))
+            .map(|_| rng.gen_range(0..costs.len()))
+            .min_by(|&a, &b| costs[a].total_cmp(&costs[b]))
+            .expect("tournament has at least one entrant")
+    }
+
+    // Blend crossover (BLX-0.25): each gene is drawn from the parents'
+    // interval widened by a quarter on both sides.
+    fn crossover(&self, a: &[f32], b: &[f32], rng: &mut StdRng) -> Vec<f32> {
+        self.genes
+            .iter()
+            .zip(a.iter().zip(b))
+            .map(|(gene, (&x, &y))| {
+                let t = rng.gen_range(-0.25..=1.25);
+                (x + t * (y - x)).clamp(gene.min, gene.max)
+            })
+            .collect()
+    }
+
+    fn mutate(&self, genes: &mut [f32], rng: &mut StdRng) {
+        for (value, gene) in genes.iter_mut().zip(&self.genes) {
+            if rng.r#gen::<f32>() < self.params.mutation_rate {
+                let sigma = self.params.mutation_scale * (gene.max - gene.min);
+                *value = (*value + sigma * standard_normal(rng)).clamp(gene.min, gene.max);
+            }
+        }
+    }
+}
+
+// Box-Muller transform.
+fn standard_normal(rng: &mut StdRng) -> f32 {
+    let u: f32 = rng.gen_range(f32::EPSILON..1.0);
+    let v: f32 = rng.r#gen();
+    (-2.0 * u.ln()).sqrt() * (std::f32::consts::TAU * v).cos()
+}
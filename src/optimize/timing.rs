@@ -0,0 +1,125 @@
+use crate::control::actuated::{ActuatedController, ActuatedParams};
+use crate::control::controller::SignalController;
+use crate::control::demand_based::{DemandController, DemandParams};
+use crate::control::fixed_time::FixedTimeController;
+use crate::control::plan::SignalPlan;
+use crate::control::webster::WebsterParams;
+use crate::manager::simulation::Simulation;
+use crate::optimize::genetic::Gene;
+use crate::types::Origin;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    Delay,
+    Throughput,
+}
+
+impl Objective {
+    // Lower is better for the search. Delay is averaged over every arrival,
+    // so leaving vehicles stranded on the road or in entry queues costs too.
+    pub fn cost(self, simulation: &Simulation) -> f32 {
+        match self {
+            Objective::Delay => simulation.total_delay() / simulation.arrived().max(1) as f32,
+            Objective::Throughput => -(simulation.statistics().total().completed as f32),
+        }
+    }
+}
+
+// What the search tunes and how a gene vector maps onto a controller. Each
+// target carries the parameters the search starts from.
+#[derive(Debug, Clone)]
+pub enum TimingTarget {
+    // Cycle length and green splits of a fixed-time plan; the template's
+    // phases and clearance times are kept, and the cycle stays within
+    // Webster's limits.
+    Plan(SignalPlan, WebsterParams),
+    Demand(DemandParams),
+    Actuated(ActuatedParams),
+}
+
+impl TimingTarget {
+    pub fn genes(&self) -> Vec<Gene> {
+        match self {
+            TimingTarget::Plan(template, limits) => {
+                let clearance = Self::clearance(template);
+                let min_cycle = limits.min_cycle.max(clearance + limits.min_green * template.phases.len() as f32);
+                let total_green: f32 = template.phases.iter().map(|p| p.green).sum();
+
+                let mut genes = vec![Gene::new("cycle", min_cycle, limits.max_cycle.max(min_cycle), template.cycle_length())];
+                genes.extend(template.phases.iter().enumerate().map(|(i, p)| {
+                    Gene::new(format!("split {}", i + 1), 0.05, 1.0, p.green / total_green)
+                }));
+                genes
+            }
+            TimingTarget::Demand(start) => vec![
+                Gene::new("min_green", 0.5, 10.0, start.min_green),
+                // One max green for all approaches, starting from the longest.
+                Gene::new("max_green", 10.0, 60.0, start.max_green.values().copied().fold(0.0, f32::max)),
+                Gene::new("max_wait", 20.0, 120.0, start.max_wait),
+                Gene::new("wait_weight", 0.0, 2.0, start.wait_weight),
+            ],
+            TimingTarget::Actuated(start) => vec![
+                Gene::new("min_green", 2.0, 15.0, start.min_green),
+                Gene::new("max_green", 10.0, 60.0, start.max_green),
+                Gene::new("passage_time", 0.5, 5.0, start.passage_time),
+            ],
+        }
+    }
+
+    pub fn controller(&self, genes: &[f32]) -> Box<dyn SignalController> {
+        match self {
+            TimingTarget::Plan(template, limits) => Box::new(FixedTimeController::new(Self::plan(template, limits, genes))),
+            TimingTarget::Demand(_) => Box::new(DemandController::with_params(Self::demand_params(genes))),
+            TimingTarget::Actuated(_) => Box::new(ActuatedController::with_params(Self::actuated_params(genes))),
+        }
+    }
+
+    // The tuned plan or controller parameters as a TOML file.
+    pub fn to_toml(&self, genes: &[f32]) -> String {
+        let text = match self {
+            TimingTarget::Plan(template, limits) => return Self::plan(template, limits, genes).to_toml(),
+            TimingTarget::Demand(_) => toml::to_string_pretty(&Self::demand_params(genes)),
+            TimingTarget::Actuated(_) => toml::to_string_pretty(&Self::actuated_params(genes)),
+        };
+        text.expect("controller parameters always serialize")
+    }
+
+    fn clearance(plan: &SignalPlan) -> f32 {
+        plan.phases.iter().map(|p| p.amber + p.all_red).sum()
+    }
+
+    // Greens share what the cycle leaves after clearance in proportion to the
+    // split genes; the cycle is then whatever the rounded greens add up to.
+    fn plan(template: &SignalPlan, limits: &WebsterParams, genes: &[f32]) -> SignalPlan {
+        let (cycle, splits) = (genes[0], &genes[1..]);
+        let available = cycle - Self::clearance(template);
+        let weight: f32 = splits.iter().sum();
+
+        let mut plan = template.clone();
+        for (phase, split) in plan.phases.iter_mut().zip(splits) {
+            let green = (available * split / weight).max(limits.min_green);
+            phase.green = (green * 10.0).round() / 10.0;
+        }
+        plan.cycle = Some(plan.phases.iter().map(|p| p.duration()).sum());
+        plan
+    }
+
+    fn demand_params(genes: &[f32]) -> DemandParams {
+        DemandParams {
+            min_green: genes[0],
+            max_green: Origin::ALL.into_iter().map(|o| (o, genes[1])).collect(),
+            max_wait: genes[2],
+            wait_weight: genes[3],
+        }
+    }
+
+    // The gene ranges overlap, so a max green below the min green is raised
+    // to it; the emitted parameters then load again.
+    fn actuated_params(genes: &[f32]) -> ActuatedParams {
+        ActuatedParams {
+            min_green: genes[0],
+            max_green: genes[1].max(genes[0]),
+            passage_time: genes[2],
+        }
+    }
+}
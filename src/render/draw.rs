@@ -1,52 +1,53 @@
 use macroquad::prelude::*;
-use crate::app_config::*;
+use crate::app_config::SimConfig;
 use crate::render::palette::*;
 
 pub struct Renderer;
 
 impl Renderer {
-    pub fn draw_environment(center: (f32, f32)) {
+    pub fn draw_environment(center: (f32, f32), config: &SimConfig) {
         let (w, h) = (screen_width(), screen_height());
+        let (road_width, lane_offset) = (config.road_width, config.lane_offset);
         
         clear_background(COLOR_GRASS);
 
         draw_rectangle(
-            center.0 - ROAD_WIDTH / 2.0,
+            center.0 - road_width / 2.0,
             0.0,
-            ROAD_WIDTH,
+            road_width,
             h,
             COLOR_ASPHALT,
         );
         draw_rectangle(
             0.0,
-            center.1 - ROAD_WIDTH / 2.0,
+            center.1 - road_width / 2.0,
             w,
-            ROAD_WIDTH,
+            road_width,
             COLOR_ASPHALT,
         );
         
         draw_rectangle(
-            center.0 - ROAD_WIDTH / 2.0,
-            center.1 - ROAD_WIDTH / 2.0,
-            ROAD_WIDTH,
-            ROAD_WIDTH,
+            center.0 - road_width / 2.0,
+            center.1 - road_width / 2.0,
+            road_width,
+            road_width,
             COLOR_ASPHALT,
         );
 
         let dash_length = 20.0;
         let dash_gap = 20.0;
 
-        draw_line(center.0 - 2.0, 0.0, center.0 - 2.0, center.1 - ROAD_WIDTH / 2.0, 2.0, COLOR_MARKING_YELLOW);
-        draw_line(center.0 + 2.0, 0.0, center.0 + 2.0, center.1 - ROAD_WIDTH / 2.0, 2.0, COLOR_MARKING_YELLOW);
+        draw_line(center.0 - 2.0, 0.0, center.0 - 2.0, center.1 - road_width / 2.0, 2.0, COLOR_MARKING_YELLOW);
+        draw_line(center.0 + 2.0, 0.0, center.0 + 2.0, center.1 - road_width / 2.0, 2.0, COLOR_MARKING_YELLOW);
         
-        draw_line(center.0 - 2.0, center.1 + ROAD_WIDTH / 2.0, center.0 - 2.0, h, 2.0, COLOR_MARKING_YELLOW);
-        draw_line(center.0 + 2.0, center.1 + ROAD_WIDTH / 2.0, center.0 + 2.0, h, 2.0, COLOR_MARKING_YELLOW);
+        draw_line(center.0 - 2.0, center.1 + road_width / 2.0, center.0 - 2.0, h, 2.0, COLOR_MARKING_YELLOW);
+        draw_line(center.0 + 2.0, center.1 + road_width / 2.0, center.0 + 2.0, h, 2.0, COLOR_MARKING_YELLOW);
 
-        draw_line(0.0, center.1 - 2.0, center.0 - ROAD_WIDTH / 2.0, center.1 - 2.0, 2.0, COLOR_MARKING_YELLOW);
-        draw_line(0.0, center.1 + 2.0, center.0 - ROAD_WIDTH / 2.0, center.1 + 2.0, 2.0, COLOR_MARKING_YELLOW);
+        draw_line(0.0, center.1 - 2.0, center.0 - road_width / 2.0, center.1 - 2.0, 2.0, COLOR_MARKING_YELLOW);
+        draw_line(0.0, center.1 + 2.0, center.0 - road_width / 2.0, center.1 + 2.0, 2.0, COLOR_MARKING_YELLOW);
 
-        draw_line(center.0 + ROAD_WIDTH / 2.0, center.1 - 2.0, w, center.1 - 2.0, 2.0, COLOR_MARKING_YELLOW);
-        draw_line(center.0 + ROAD_WIDTH / 2.0, center.1 + 2.0, w, center.1 + 2.0, 2.0, COLOR_MARKING_YELLOW);
+        draw_line(center.0 + road_width / 2.0, center.1 - 2.0, w, center.1 - 2.0, 2.0, COLOR_MARKING_YELLOW);
+        draw_line(center.0 + road_width / 2.0, center.1 + 2.0, w, center.1 + 2.0, 2.0, COLOR_MARKING_YELLOW);
 
         let draw_dashed_line = |x1: f32, y1: f32, x2: f32, y2: f32| {
             let dx = x2 - x1;
@@ -69,20 +70,20 @@ impl Renderer {
             }
         };
 
-        draw_dashed_line(center.0 - LANE_OFFSET, 0.0, center.0 - LANE_OFFSET, center.1 - ROAD_WIDTH / 2.0);
-        draw_dashed_line(center.0 + LANE_OFFSET, 0.0, center.0 + LANE_OFFSET, center.1 - ROAD_WIDTH / 2.0);
-        draw_dashed_line(center.0 - LANE_OFFSET, center.1 + ROAD_WIDTH / 2.0, center.0 - LANE_OFFSET, h);
-        draw_dashed_line(center.0 + LANE_OFFSET, center.1 + ROAD_WIDTH / 2.0, center.0 + LANE_OFFSET, h);
+        draw_dashed_line(center.0 - lane_offset, 0.0, center.0 - lane_offset, center.1 - road_width / 2.0);
+        draw_dashed_line(center.0 + lane_offset, 0.0, center.0 + lane_offset, center.1 - road_width / 2.0);
+        draw_dashed_line(center.0 - lane_offset, center.1 + road_width / 2.0, center.0 - lane_offset, h);
+        draw_dashed_line(center.0 + lane_offset, center.1 + road_width / 2.0, center.0 + lane_offset, h);
 
-        draw_dashed_line(0.0, center.1 - LANE_OFFSET, center.0 - ROAD_WIDTH / 2.0, center.1 - LANE_OFFSET);
-        draw_dashed_line(0.0, center.1 + LANE_OFFSET, center.0 - ROAD_WIDTH / 2.0, center.1 + LANE_OFFSET);
-        draw_dashed_line(center.0 + ROAD_WIDTH / 2.0, center.1 - LANE_OFFSET, w, center.1 - LANE_OFFSET);
-        draw_dashed_line(center.0 + ROAD_WIDTH / 2.0, center.1 + LANE_OFFSET, w, center.1 + LANE_OFFSET);
+        draw_dashed_line(0.0, center.1 - lane_offset, center.0 - road_width / 2.0, center.1 - lane_offset);
+        draw_dashed_line(0.0, center.1 + lane_offset, center.0 - road_width / 2.0, center.1 + lane_offset);
+        draw_dashed_line(center.0 + road_width / 2.0, center.1 - lane_offset, w, center.1 - lane_offset);
+        draw_dashed_line(center.0 + road_width / 2.0, center.1 + lane_offset, w, center.1 + lane_offset);
 
         let stop_width = 4.0;
-        draw_line(center.0, center.1 - ROAD_WIDTH / 2.0, center.0 - ROAD_WIDTH / 2.0, center.1 - ROAD_WIDTH / 2.0, stop_width, COLOR_MARKING_WHITE);
-        draw_line(center.0, center.1 + ROAD_WIDTH / 2.0, center.0 + ROAD_WIDTH / 2.0, center.1 + ROAD_WIDTH / 2.0, stop_width, COLOR_MARKING_WHITE);
-        draw_line(center.0 - ROAD_WIDTH / 2.0, center.1, center.0 - ROAD_WIDTH / 2.0, center.1 + ROAD_WIDTH / 2.0, stop_width, COLOR_MARKING_WHITE);
-        draw_line(center.0 + ROAD_WIDTH / 2.0, center.1, center.0 + ROAD_WIDTH / 2.0, center.1 - ROAD_WIDTH / 2.0, stop_width, COLOR_MARKING_WHITE);
+        draw_line(center.0, center.1 - road_width / 2.0, center.0 - road_width / 2.0, center.1 - road_width / 2.0, stop_width, COLOR_MARKING_WHITE);
+        draw_line(center.0, center.1 + road_width / 2.0, center.0 + road_width / 2.0, center.1 + road_width / 2.0, stop_width, COLOR_MARKING_WHITE);
+        draw_line(center.0 - road_width / 2.0, center.1, center.0 - road_width / 2.0, center.1 + road_width / 2.0, stop_width, COLOR_MARKING_WHITE);
+        draw_line(center.0 + road_width / 2.0, center.1, center.0 + road_width / 2.0, center.1 - road_width / 2.0, stop_width, COLOR_MARKING_WHITE);
     }
 }
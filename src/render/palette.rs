@@ -8,7 +8,12 @@ pub const COLOR_MARKING_YELLOW: Color = Color::new(0.6, 0.6, 0.6, 1.0);
 
 pub const COLOR_LIGHT_HOUSING: Color = Color::new(0.0, 0.0, 0.0, 1.0);
 pub const COLOR_LIGHT_GO: Color = Color::new(0.2, 0.8, 0.2, 1.0);
+pub const COLOR_LIGHT_AMBER: Color = Color::new(1.0, 0.7, 0.0, 1.0);
 pub const COLOR_LIGHT_STOP: Color = Color::new(0.9, 0.1, 0.1, 1.0);
+pub const COLOR_LIGHT_OFF: Color = Color::new(0.2, 0.2, 0.2, 1.0);
+
+pub const COLOR_DETECTOR_PRESENCE: Color = Color::new(0.3, 0.7, 1.0, 1.0);
+pub const COLOR_DETECTOR_PASSAGE: Color = Color::new(1.0, 0.4, 0.9, 1.0);
 
 pub const COLOR_CAR_TEAL: Color = Color::new(0.1, 0.7, 0.7, 1.0);
 pub const COLOR_CAR_CORAL: Color = Color::new(1.0, 0.5, 0.4, 1.0);
@@ -17,6 +22,10 @@ pub const COLOR_CAR_MUSTARD: Color = Color::new(0.9, 0.8, 0.2, 1.0);
 pub const COLOR_CAR_BERRY: Color = Color::new(0.8, 0.2, 0.4, 1.0);
 pub const COLOR_CAR_SLATE: Color = Color::new(0.4, 0.5, 0.6, 1.0);
 
+pub const COLOR_TRUCK_CARGO: Color = Color::new(0.75, 0.75, 0.72, 1.0);
+pub const COLOR_BUS: Color = Color::new(0.95, 0.65, 0.1, 1.0);
+pub const COLOR_RIDER: Color = Color::new(0.15, 0.15, 0.15, 1.0);
+
 pub const CAR_COLORS: [Color; 6] = [
     COLOR_CAR_TEAL,
     COLOR_CAR_CORAL,
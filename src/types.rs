@@ -1,14 +1,69 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Route {
     Right,
     Left,
     Straight,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+impl Route {
+    pub const ALL: [Route; 3] = [Route::Left, Route::Straight, Route::Right];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Origin {
     North,
     South,
     West,
     East,
 }
+
+impl Origin {
+    pub const ALL: [Origin; 4] = [Origin::North, Origin::South, Origin::East, Origin::West];
+}
+
+// One lane's movement through the box: where it comes from and where it turns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Movement {
+    pub origin: Origin,
+    pub route: Route,
+}
+
+impl Movement {
+    pub fn new(origin: Origin, route: Route) -> Self {
+        Self { origin, route }
+    }
+
+    pub fn all() -> impl Iterator<Item = Movement> {
+        Origin::ALL
+            .into_iter()
+            .flat_map(|origin| Route::ALL.into_iter().map(move |route| Movement::new(origin, route)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignalAspect {
+    Green,
+    // Go, but yield to conflicting traffic (a flashing amber arrow).
+    Permissive,
+    Amber,
+    Red,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VehicleClass {
+    Car,
+    Truck,
+    Bus,
+    Motorcycle,
+}
+
+impl VehicleClass {
+    pub const ALL: [VehicleClass; 4] = [
+        VehicleClass::Car,
+        VehicleClass::Truck,
+        VehicleClass::Bus,
+        VehicleClass::Motorcycle,
+    ];
+}
@@ -0,0 +1,71 @@
+use road_intersection::control::demand_based::DemandParams;
+use road_intersection::{ControllerSpec, Scenario, SimConfig, VehicleClass};
+
+#[test]
+fn sets_section_settings_by_short_or_full_name() {
+    let mut config = SimConfig::default();
+    config.set("min_green=6").unwrap();
+    config.set("timing.amber_time=3").unwrap();
+    assert_eq!(config.controller.min_green, 6.0);
+    assert_eq!(config.timing.amber_time, 3.0);
+    assert!(config.set("controller=6").is_err());
+    assert!(config.set("controller.road_width=6").is_err());
+}
+
+#[test]
+fn rejects_a_road_too_narrow_for_the_widest_class() {
+    let mut config = SimConfig::default();
+    // Fits a car, but trucks and buses are wider.
+    config.set("lane_center=62").unwrap();
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn scenario_parameters_default_to_its_config() {
+    let scenario = Scenario::from_toml(
+        r#"
+        [controller]
+        kind = "demand"
+        max_wait = 30
+
+        [config.controller]
+        min_green = 3
+        "#,
+    )
+    .unwrap();
+
+    let ControllerSpec::Demand(params) = &scenario.controller else {
+        panic!("expected the demand controller");
+    };
+    assert_eq!(params.min_green, 3.0);
+    assert_eq!(params.max_wait, 30.0);
+    assert_eq!(params.wait_weight, DemandParams::default().wait_weight);
+}
+
+#[test]
+fn rejects_misspelled_scenario_and_controller_settings() {
+    assert!(Scenario::from_toml("sede = 4").is_err());
+    assert!(Scenario::from_toml("[controller]\nkind = \"actuated\"\nmin_gren = 4\n").is_err());
+    assert!(Scenario::from_toml("[controller]\nkind = \"demand\"\nmax_gren = { North = 30 }\n").is_err());
+    assert!(Scenario::from_toml("[controller]\nkind = \"actuated\"\nmin_green = 4\n").is_ok());
+}
+
+#[test]
+fn rejects_infinite_class_weights_and_event_times() {
+    assert!(Scenario::from_toml("[class_mix]\nCar = inf\n").is_err());
+    assert!(Scenario::from_toml("[[events]]\nat = inf\naction = \"spawn\"\norigin = \"North\"\ncount = 3\n").is_err());
+    assert!(Scenario::from_toml("[[events]]\nat = 10\naction = \"spawn\"\norigin = \"North\"\ncount = 3\n").is_ok());
+}
+
+#[test]
+fn sets_vehicle_classes_from_the_config() {
+    let mut config = SimConfig::default();
+    config.set("bus_length=60").unwrap();
+    config.set("classes.car_spawn_weight=0").unwrap();
+    assert_eq!(VehicleClass::Bus.spec(&config).length, 60.0);
+    assert_eq!(VehicleClass::Car.spec(&config).spawn_weight, 0.0);
+    config.validate().unwrap();
+
+    config.set("truck_width=100").unwrap();
+    assert!(config.validate().is_err());
+}
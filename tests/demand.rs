@@ -0,0 +1,39 @@
+use road_intersection::{DemandProfile, Headway};
+
+const PEAK: &str = r#"
+[[periods]]
+start = 0
+rates = { North = 300, South = 200 }
+"#;
+
+#[test]
+fn accepts_a_finite_profile() {
+    let profile = DemandProfile::from_toml(PEAK).unwrap();
+    assert_eq!(profile.periods.len(), 1);
+}
+
+#[test]
+fn rejects_infinite_and_negative_rates() {
+    for rate in ["inf", "nan", "-5", "1e12"] {
+        let text = PEAK.replace("North = 300", &format!("North = {}", rate));
+        assert!(DemandProfile::from_toml(&text).is_err(), "rate {} was accepted", rate);
+    }
+}
+
+#[test]
+fn rejects_unordered_periods() {
+    let text = format!("{}\n[[periods]]\nstart = 0\nrates = {{ East = 100 }}\n", PEAK);
+    assert!(DemandProfile::from_toml(&text).is_err());
+}
+
+#[test]
+fn rejects_non_finite_minimum_headways() {
+    for min in ["inf", "nan", "-1"] {
+        assert!(format!("shifted:{}", min).parse::<Headway>().is_err(), "minimum {} was accepted", min);
+    }
+}
+
+#[test]
+fn rejects_an_invalid_constant_rate() {
+    assert!(DemandProfile::constant(-100.0, Headway::Exponential).validate().is_err());
+}
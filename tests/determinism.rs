@@ -0,0 +1,37 @@
+use road_intersection::app_config::HEADLESS_BOUNDS;
+use road_intersection::{SimConfig, Simulation};
+
+// Spawns every half second, so vehicles queue, turn and leave within the run.
+fn run(seed: u64, steps: usize) -> Simulation {
+    let config = SimConfig::default();
+    let dt = config.fixed_dt;
+    let mut simulation = Simulation::with_config(HEADLESS_BOUNDS, seed, config);
+    let mut next_spawn = 0.0;
+    for _ in 0..steps {
+        if simulation.time() >= next_spawn {
+            simulation.spawn_random();
+            next_spawn += 0.5;
+        }
+        simulation.step(dt);
+    }
+    simulation
+}
+
+fn positions(simulation: &Simulation) -> Vec<(usize, (f32, f32), f32)> {
+    simulation.vehicles().iter().map(|v| (v.id, v.pos, v.heading)).collect()
+}
+
+#[test]
+fn same_seed_gives_the_same_run() {
+    let (a, b) = (run(7, 3600), run(7, 3600));
+
+    assert!(!a.vehicles().is_empty());
+    assert!(a.statistics().total().completed > 0);
+    assert_eq!(positions(&a), positions(&b));
+    assert_eq!(a.statistics(), b.statistics());
+}
+
+#[test]
+fn different_seeds_give_different_runs() {
+    assert_ne!(positions(&run(7, 1200)), positions(&run(8, 1200)));
+}
@@ -0,0 +1,56 @@
+use road_intersection::app_config::HEADLESS_BOUNDS;
+use road_intersection::{Movement, Origin, Route, Simulation};
+
+fn arrivals(simulation: &Simulation, origin: Origin) -> usize {
+    Route::ALL.into_iter().map(|route| simulation.statistics().arrivals(Movement::new(origin, route))).sum()
+}
+
+#[test]
+fn queues_a_burst_instead_of_dropping_it() {
+    let mut simulation = Simulation::new(HEADLESS_BOUNDS, 1);
+    let dt = simulation.config().fixed_dt;
+    for _ in 0..30 {
+        simulation.spawn(Origin::North);
+    }
+    simulation.step(dt);
+
+    let queued = simulation.entry_queue(Origin::North);
+    assert!(queued > 0);
+    assert!(simulation.entry_wait(Origin::North) >= 0.0);
+    assert_eq!(arrivals(&simulation, Origin::North), 30);
+    assert_eq!(simulation.statistics().total().spawned + queued, 30);
+}
+
+#[test]
+fn drains_the_queue_over_time() {
+    let mut simulation = Simulation::new(HEADLESS_BOUNDS, 1);
+    let dt = simulation.config().fixed_dt;
+    for _ in 0..30 {
+        simulation.spawn(Origin::North);
+    }
+    for _ in 0..(300.0 / dt) as usize {
+        simulation.step(dt);
+    }
+
+    assert_eq!(simulation.entry_queue(Origin::North), 0);
+    assert_eq!(simulation.statistics().total().spawned, 30);
+}
+
+#[test]
+fn lists_queued_vehicles_in_approach_order() {
+    let mut simulation = Simulation::new(HEADLESS_BOUNDS, 1);
+    let dt = simulation.config().fixed_dt;
+    for origin in [Origin::West, Origin::North, Origin::East, Origin::South] {
+        for _ in 0..20 {
+            simulation.spawn(origin);
+        }
+    }
+    simulation.step(dt);
+
+    let origins: Vec<Origin> = simulation.queued_vehicles().map(|v| v.origin).collect();
+    let expected: Vec<Origin> = Origin::ALL
+        .into_iter()
+        .flat_map(|o| std::iter::repeat_n(o, simulation.entry_queue(o)))
+        .collect();
+    assert_eq!(origins, expected);
+}
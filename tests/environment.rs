@@ -0,0 +1,17 @@
+use road_intersection::{EnvConfig, EnvError, SignalEnv};
+
+#[test]
+fn rejects_out_of_range_actions() {
+    let mut env = SignalEnv::new(EnvConfig::default());
+    let count = env.action_count();
+
+    assert_eq!(
+        env.step(count).unwrap_err(),
+        EnvError::InvalidAction { action: count, action_count: count }
+    );
+    assert_eq!(env.simulation().time(), 0.0);
+
+    let (observation, _, done) = env.step(0).unwrap();
+    assert!(observation.time > 0.0);
+    assert!(!done);
+}
@@ -0,0 +1,22 @@
+use road_intersection::app_config::HEADLESS_BOUNDS;
+use road_intersection::{DemandProfile, Headway, Simulation};
+
+// Seed 3 at 500 veh/h once locked up after about 370 s: two opposing left
+// turns crossed on the amber ending their permissive interval and met
+// head-on in the box.
+#[test]
+fn keeps_completing_vehicles_under_permissive_lefts() {
+    let mut simulation = Simulation::new(HEADLESS_BOUNDS, 3);
+    simulation.set_demand(DemandProfile::constant(500.0, Headway::Exponential));
+    let dt = simulation.config().fixed_dt;
+
+    let mut completed = 0;
+    for minute in 1..=8 {
+        while simulation.time() < minute as f32 * 60.0 {
+            simulation.step(dt);
+        }
+        let now = simulation.statistics().total().completed;
+        assert!(now > completed, "no vehicle completed in minute {}", minute);
+        completed = now;
+    }
+}
@@ -0,0 +1,28 @@
+use road_intersection::control::demand_based::DemandController;
+use road_intersection::HeadlessRun;
+
+#[test]
+fn completes_and_accounts_for_every_vehicle() {
+    let run = HeadlessRun {
+        duration: 120.0,
+        ..HeadlessRun::new(3)
+    };
+    let simulation = run.run(Box::new(DemandController::new())).unwrap();
+    let total = simulation.statistics().total();
+
+    assert!((simulation.time() - run.duration).abs() < run.config.fixed_dt);
+    assert!(total.spawned > 0);
+    assert!(total.completed > 0);
+    assert!(total.completed <= total.spawned);
+}
+
+#[test]
+fn repeats_with_the_same_seed() {
+    let run = HeadlessRun {
+        duration: 60.0,
+        ..HeadlessRun::new(3)
+    };
+    let a = run.run(Box::new(DemandController::new())).unwrap();
+    let b = run.run(Box::new(DemandController::new())).unwrap();
+    assert_eq!(a.statistics(), b.statistics());
+}
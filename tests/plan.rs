@@ -0,0 +1,84 @@
+use road_intersection::app_config::HEADLESS_BOUNDS;
+use road_intersection::control::fixed_time::FixedTimeController;
+use road_intersection::{ConflictMatrix, ControllerSpec, Scenario, SignalPlan, SimConfig, Simulation};
+
+// North and South straight never meet in the default layout, but a wide
+// enough conflict distance makes the opposing lanes conflict.
+const OPPOSING_STRAIGHTS: &str = r#"
+name = "opposing straights"
+
+[[phases]]
+movements = [{ origin = "North", route = "Straight" }, { origin = "South", route = "Straight" }]
+green = 20
+
+[[phases]]
+approaches = ["East"]
+green = 20
+"#;
+
+fn wide_conflicts() -> SimConfig {
+    let mut config = SimConfig::default();
+    config.set("conflict_distance=60").unwrap();
+    config
+}
+
+#[test]
+fn rejects_crossing_movements_in_one_phase() {
+    let text = OPPOSING_STRAIGHTS.replace(r#"origin = "South", route = "Straight""#, r#"origin = "East", route = "Straight""#);
+    assert!(SignalPlan::from_toml(&text).is_err());
+}
+
+#[test]
+fn checks_against_the_layout_conflicts() {
+    let plan = SignalPlan::from_toml(OPPOSING_STRAIGHTS).unwrap();
+    assert!(plan.validate_against(&ConflictMatrix::from_geometry(&wide_conflicts())).is_err());
+}
+
+#[test]
+fn keeps_the_controller_when_a_plan_is_rejected() {
+    let plan = SignalPlan::from_toml(OPPOSING_STRAIGHTS).unwrap();
+    let mut simulation = Simulation::with_config(HEADLESS_BOUNDS, 0, wide_conflicts());
+    let before = simulation.traffic_system().controller_name().to_string();
+
+    assert!(simulation.set_controller(Box::new(FixedTimeController::new(plan))).is_err());
+    assert_eq!(simulation.traffic_system().controller_name(), before);
+}
+
+#[test]
+fn scenario_plans_follow_the_scenario_config() {
+    let plan = OPPOSING_STRAIGHTS.replace("[[phases]]", "[[controller.phases]]").replace("name =", "controller.name =");
+    let text = format!("controller.kind = \"fixed-time\"\n{}", plan);
+    assert!(Scenario::from_toml(&text).is_ok());
+
+    let text = format!("{}\n[config]\nconflict_distance = 60\n", text);
+    assert!(Scenario::from_toml(&text).is_err());
+}
+
+#[test]
+fn rejects_non_finite_durations_and_offsets() {
+    for (from, to) in [("green = 20\n", "green = inf\n"), ("green = 20\n", "green = 20\namber = nan\n"), ("name =", "offset = inf\nname =")] {
+        let text = OPPOSING_STRAIGHTS.replacen(from, to, 1);
+        assert!(SignalPlan::from_toml(&text).is_err(), "accepted {}", to);
+    }
+    assert!(SignalPlan::from_toml(&format!("cycle = nan\n{}", OPPOSING_STRAIGHTS)).is_err());
+}
+
+#[test]
+fn takes_omitted_clearances_from_the_config() {
+    let mut config = SimConfig::default();
+    config.set("amber_time=4.5").unwrap();
+    config.set("all_red_time=2").unwrap();
+
+    let plan = SignalPlan::from_toml_with(OPPOSING_STRAIGHTS, &config).unwrap();
+    assert!(plan.phases.iter().all(|p| p.amber == 4.5 && p.all_red == 2.0));
+
+    let scenario = Scenario::from_toml_with(
+        &format!("[controller]\nkind = \"fixed-time\"\n{}", OPPOSING_STRAIGHTS.replace("[[phases]]", "[[controller.phases]]")),
+        |_| Ok(config.clone()),
+    )
+    .unwrap();
+    let ControllerSpec::FixedTime(plan) = &scenario.controller else {
+        panic!("expected a fixed-time controller");
+    };
+    assert!(plan.phases.iter().all(|p| p.amber == 4.5 && p.all_red == 2.0));
+}
@@ -0,0 +1,27 @@
+use road_intersection::app_config::HEADLESS_BOUNDS;
+use road_intersection::control::actuated::ActuatedParams;
+use road_intersection::optimize::timing::{Objective, TimingTarget};
+use road_intersection::{Origin, Simulation};
+
+#[test]
+fn emitted_actuated_params_load_again() {
+    let target = TimingTarget::Actuated(ActuatedParams::default());
+    let text = target.to_toml(&[14.0, 10.0, 2.0]);
+    let params: ActuatedParams = toml::from_str(&text).unwrap();
+    assert!(params.validate().is_ok(), "{}", text);
+}
+
+#[test]
+fn delay_cost_counts_vehicles_that_never_finished() {
+    let mut simulation = Simulation::new(HEADLESS_BOUNDS, 1);
+    let dt = simulation.config().fixed_dt;
+    for _ in 0..20 {
+        simulation.spawn(Origin::North);
+    }
+    for _ in 0..60 {
+        simulation.step(dt);
+    }
+
+    assert_eq!(simulation.statistics().total().completed, 0);
+    assert!(Objective::Delay.cost(&simulation) > 0.0);
+}
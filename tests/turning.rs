@@ -0,0 +1,10 @@
+use road_intersection::TurningMatrix;
+
+#[test]
+fn rejects_non_finite_and_negative_ratios() {
+    for ratio in ["inf", "nan", "-0.5"] {
+        let text = format!("[North]\nleft = {}\nstraight = 0.5\nright = 0.2\n", ratio);
+        assert!(TurningMatrix::from_toml(&text).is_err(), "ratio {} was accepted", ratio);
+    }
+    assert!(TurningMatrix::from_toml("[North]\nleft = 0.3\nstraight = 0.5\nright = 0.2\n").is_ok());
+}